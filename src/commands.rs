@@ -1,250 +1,1141 @@
 pub mod commands {
-    use std::{io, fs};
-    use std::path::Path;
-    use chrono::{NaiveDateTime, Datelike, NaiveDate, Local, TimeZone};
-    use crate::path_parser::path_parser::{get_dates_from_path, get_path_without_dir_names};
-    use std::io::{Error, ErrorKind};
-    use crate::domain::domain::NoExifConfig;
-    use crate::files::files::get_files_from_path;
-    use crate::exif::exif::get_date_created_from_file_exif;
-
-    const DEST_DATETIME_FORMAT: &str = "%Y-%m-%d__%H-%M-%S";
-    const DEST_DATE_FORMAT: &str = "%Y-%m-%d";
-
-    const JANUARY: &str = "Январь";
-    const FEBRUARY: &str = "Февраль";
-    const MARCH: &str = "Март";
-    const APRIL: &str = "Апрель";
-    const MAY: &str = "Май";
-    const JUNE: &str = "Июнь";
-    const JULY: &str = "Июль";
-    const AUGUST: &str = "Август";
-    const SEPTEMBER: &str = "Сентябрь";
-    const OCTOBER: &str = "Октябрь";
-    const NOVEMBER: &str = "Ноябрь";
-    const DECEMBER: &str = "Декабрь";
-    const UNKNOWN_MONTH_NAME: &str = "Неизвестный";
-
-    pub fn reorganize_files(src_path: &str, dest_path: &str,
-                            file_ext_filter: &Vec<String>,
+    use std::{io, fs, fmt};
+    use std::path::{Path, PathBuf};
+    use std::ffi::{OsStr, OsString};
+    use std::collections::{HashMap, BTreeMap};
+    use std::sync::Mutex;
+    use std::sync::mpsc::{self, Sender, Receiver};
+    use std::thread;
+    use chrono::Datelike;
+    use std::io::Error;
+    use crate::domain::domain::{FileFilter, NoExifConfig, ExifConfig, ReorganizeConfig, DateSource,
+                                PlacementMode, ConflictPolicy};
+    use crate::files::files::{walk_files, move_file, create_symlink, reflink_file,
+                              files_are_equal, get_content_hash, RawGroups};
+    use crate::dates::dates::{resolve_file_date, FileDate, FileDateInfo};
+    use crate::template::template::{PathTemplate, TemplateValues};
+    use crate::locale::locale::MonthNames;
+    use crate::encoding::encoding::decode_file_name;
+    use blake3::Hash;
+
+    /// File placement planned by `reorg` command.
+    pub struct PlannedFile {
+        pub src_file_path: PathBuf,
+        pub dest_file_path: PathBuf,
+        pub date_source: DateSource
+    }
+
+    /// Destination file already exists (or has been already planned for other file).
+    pub struct Collision {
+        pub src_file_path: PathBuf,
+        pub dest_file_path: PathBuf,
+        pub decision: CollisionDecision
+    }
+
+    pub enum CollisionDecision {
+        SkippedIdentical,
+        Skipped,
+        Overwritten,
+        Renamed(PathBuf),
+        Failed,
+        ReplacedWithLarger,
+        KeptExistingLarger
+    }
+
+    impl fmt::Display for CollisionDecision {
+        fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+            match self {
+                CollisionDecision::SkippedIdentical => write!(f, "skipped (identical content)"),
+                CollisionDecision::Skipped => write!(f, "skipped"),
+                CollisionDecision::Overwritten => write!(f, "overwritten"),
+                CollisionDecision::Renamed(new_file_path) =>
+                    write!(f, "renamed to '{}'", new_file_path.display()),
+                CollisionDecision::Failed => write!(f, "failed"),
+                CollisionDecision::ReplacedWithLarger => write!(f, "replaced with larger file"),
+                CollisionDecision::KeptExistingLarger => write!(f, "kept existing larger file")
+            }
+        }
+    }
+
+    /// File with the same content as already placed one, skipped in `--dedupe` mode.
+    pub struct Duplicate {
+        pub src_file_path: PathBuf,
+
+        /// Source file which copy has been placed
+        pub original_file_path: PathBuf
+    }
+
+    /// File which date can't be determined.
+    pub struct UndatedFile {
+        pub src_file_path: PathBuf,
+
+        /// Destination in unknown directory, `None` if file has been left in source path
+        pub unknown_file_path: Option<PathBuf>
+    }
+
+    pub struct ReorganizeReport {
+        pub files_total: usize,
+
+        /// Files placed to destination or unknown directory, planned in dry-run mode
+        pub placed_files: usize,
+
+        pub undated_files: Vec<UndatedFile>,
+
+        /// Files which couldn't be placed
+        pub failed_files: Vec<PathBuf>,
+
+        /// Filled in dry-run mode only
+        pub planned_files: Vec<PlannedFile>,
+
+        pub collisions: Vec<Collision>,
+
+        /// Filled in `--dedupe` mode only
+        pub duplicates: Vec<Duplicate>,
+
+        /// Files and directories skipped by exclusion rules
+        pub excluded_paths: usize,
+
+        /// Directories and entries which couldn't be read, traversal has been continued
+        pub traversal_errors: Vec<String>,
+
+        /// Symbolic links with missing target
+        pub broken_links: Vec<PathBuf>,
+
+        /// Move mode: sources which weren't moved, because destination already has
+        /// the same content (identical file or duplicate)
+        pub left_in_place: Vec<PathBuf>,
+
+        /// Processing has been stopped by collision with `fail` policy,
+        /// files planned before collision have been placed
+        pub stopped: bool
+    }
+
+    impl ReorganizeReport {
+        /// Files skipped on collisions, duplicates and files without date left in source path
+        pub fn get_skipped_files(&self) -> usize {
+            self.files_total.saturating_sub(self.placed_files + self.failed_files.len())
+        }
+
+        /// Files without date left in source path and files which couldn't be placed
+        pub fn get_unplaced_files(&self) -> usize {
+            self.undated_files.iter()
+                .filter(|undated_file| undated_file.unknown_file_path.is_none())
+                .count() + self.failed_files.len()
+        }
+    }
+
+    struct FileDestination {
+        dest_path: PathBuf,
+        dest_file_path: PathBuf,
+        date_source: DateSource
+    }
+
+    /// Destination file path after collision resolution.
+    struct ResolvedDestination {
+        dest_file_path: PathBuf,
+
+        /// Existing destination file should be replaced
+        overwrite: bool
+    }
+
+    /// Collision policy decision for file.
+    enum Resolution {
+        Place(ResolvedDestination),
+        Skip,
+
+        /// Destination already has the same content
+        SkipIdentical,
+
+        /// Collision with `fail` policy, processing should be stopped
+        Stop
+    }
+
+    /// Date extraction task for worker.
+    struct DateJob {
+        /// Order of discovery
+        index: usize,
+        file_path: PathBuf,
+
+        /// File which date is shared by RAW group
+        group_file: Option<PathBuf>
+    }
+
+    struct ResolvedDate {
+        index: usize,
+        file_path: PathBuf,
+        file_date_info: FileDateInfo,
+
+        /// Computed in `--dedupe` mode only
+        content_hash: Option<Hash>
+    }
+
+    struct PlacementJob {
+        src_file_path: PathBuf,
+        dest_path: PathBuf,
+        dest_file_path: PathBuf,
+        overwrite: bool,
+
+        /// Files without date are always copied
+        placement_mode: PlacementMode
+    }
+
+    struct PlacementResult {
+        src_file_path: PathBuf,
+        placed: bool
+    }
+
+    /// Placements in progress. Without workers files are placed immediately.
+    struct Placements<'a> {
+        jobs: Option<Sender<PlacementJob>>,
+        results: &'a Receiver<PlacementResult>,
+        pending: usize,
+        placed_files: usize,
+        failed_files: Vec<PathBuf>,
+
+        /// Workers have stopped
+        has_errors: bool
+    }
+
+    impl<'a> Placements<'a> {
+        fn add(&mut self, placement_job: PlacementJob) {
+            match &self.jobs {
+                Some(jobs) => {
+                    if jobs.send(placement_job).is_ok() {
+                        self.pending += 1;
+
+                    } else {
+                        self.has_errors = true
+                    }
+                }
+                None => {
+                    let placement_result = place_file(placement_job);
+                    self.finish(placement_result)
+                }
+            }
+
+            while let Ok(placement_result) = self.results.try_recv() {
+                self.pending -= 1;
+                self.finish(placement_result)
+            }
+        }
+
+        /// Wait until all planned files are in destination.
+        fn wait_all(&mut self) {
+            while self.pending > 0 {
+                match self.results.recv() {
+                    Ok(placement_result) => {
+                        self.pending -= 1;
+                        self.finish(placement_result)
+                    }
+                    Err(_) => {
+                        self.has_errors = true;
+                        break
+                    }
+                }
+            }
+        }
+
+        fn finish(&mut self, placement_result: PlacementResult) {
+            if placement_result.placed {
+                self.placed_files += 1
+
+            } else {
+                self.failed_files.push(placement_result.src_file_path)
+            }
+        }
+    }
+
+    /// Content hashes of source and destination files. Each file is hashed once,
+    /// hashes are reused for duplicate detection and collision checks.
+    struct ContentHashes {
+        enabled: bool,
+
+        /// File path > content hash
+        by_path: HashMap<PathBuf, Hash>,
+
+        /// Content hash > first source file with such content
+        originals: HashMap<Hash, PathBuf>
+    }
+
+    impl ContentHashes {
+        fn get(&mut self, file_path: &Path) -> Option<Hash> {
+            if let Some(content_hash) = self.by_path.get(file_path) {
+                return Some(*content_hash)
+            }
+
+            match get_content_hash(file_path) {
+                Ok(content_hash) => {
+                    self.by_path.insert(file_path.to_path_buf(), content_hash);
+                    Some(content_hash)
+                }
+                Err(e) => {
+                    warn!("unable to get content hash for '{}': {}", file_path.display(), e);
+                    None
+                }
+            }
+        }
+
+        fn is_same_content(&mut self, src_file_path: &Path, existing_file_path: &Path) -> bool {
+            if self.enabled {
+                if let (Some(src_hash), Some(existing_hash)) = (self.get(src_file_path),
+                                                                self.get(existing_file_path)) {
+                    return src_hash == existing_hash
+                }
+            }
+
+            is_same_content(src_file_path, existing_file_path)
+        }
+    }
+
+    /// State shared by planning of all files.
+    struct PlanState<'a> {
+        /// Destination file path > source file path
+        claimed_destinations: HashMap<PathBuf, PathBuf>,
+
+        content_hashes: ContentHashes,
+        report: ReorganizeReport,
+        placements: Placements<'a>
+    }
+
+    /// Files queued for date extraction per worker, limits memory while walking
+    const QUEUED_FILES_PER_JOB: usize = 4;
+
+    pub fn reorganize_files(src_path: &Path, dest_path: &Path,
+                            file_filter: &FileFilter,
                             no_exif_config: &NoExifConfig,
-                            on_progress: fn(total: usize, current_index: usize))
-                                                                        -> Result<(), io::Error> {
-        info!("reorganize files for path '{}'", src_path);
-        info!("destination path '{}'", dest_path);
+                            exif_config: &ExifConfig,
+                            reorganize_config: &ReorganizeConfig,
+                            on_progress: fn(discovered: usize, processed: usize))
+                                                        -> Result<ReorganizeReport, io::Error> {
+        info!("reorganize files for path '{}'", src_path.display());
+        info!("destination path '{}'", dest_path.display());
+        info!("file filter: {}", file_filter.to_string());
         info!("no exif config: {}", no_exif_config.to_string());
+        info!("exif config: {}", exif_config.to_string());
+        info!("reorganize config: {}", reorganize_config.to_string());
 
-        let mut has_errors = false;
-
-        match get_files_from_path(src_path, file_ext_filter) {
-            Ok(files) => {
-                for (index, file_path_str) in files.iter().enumerate() {
-                    info!("processing file '{}'", file_path_str);
-
-                    let file_path = Path::new(&file_path_str);
-                    let file_name = file_path.file_name().unwrap().to_str().unwrap();
-
-                    match get_date_created_from_file_exif(&file_path_str) {
-                        Ok(date_created) => {
-                            match date_created {
-                                Some(file_datetime) => {
-                                    let (result_path, result_file_path) =
-                                        get_dest_path_and_filepath_with_datetime(
-                                        dest_path, file_name,
-                                        file_datetime
-                                    );
-
-                                    match reorganize_file(
-                                        file_datetime.year(), dest_path,
-                                        &file_path_str, &result_path,
-                                        &result_file_path
-                                    ) {
-                                        Ok(_) => {}
-                                        Err(_) => has_errors = true
-                                    }
-                                }
-                                None => {
-                                    warn!(
-                                        "file '{}' doesn't contain date in EXIF meta-data",
-                                        file_name
-                                    );
-
-                                    if no_exif_config.extract_dates_from_path {
-                                        match reorganize_file_without_exif(
-                                            &file_path_str, dest_path, file_name,
-                                            no_exif_config
-                                        ) {
-                                            Ok(_) => {}
-                                            Err(_) => has_errors = true
-                                        }
-                                    }
-                                }
+        let mut walker = match walk_files(src_path, file_filter) {
+            Ok(walker) => walker,
+            Err(e) => {
+                error!("unable to get files: {}", e);
+                return Err(e)
+            }
+        };
+
+        let jobs = reorganize_config.jobs.max(1);
+
+        // sequential processing keeps single file in queue
+        let max_queued_files = if jobs > 1 { jobs * QUEUED_FILES_PER_JOB } else { 1 };
+
+        let (date_job_sender, date_job_receiver) = mpsc::channel::<DateJob>();
+        let date_job_receiver = Mutex::new(date_job_receiver);
+        let (resolved_date_sender, resolved_date_receiver) = mpsc::channel::<ResolvedDate>();
+
+        let (placement_sender, placement_receiver) = mpsc::channel::<PlacementJob>();
+        let placement_receiver = Mutex::new(placement_receiver);
+        let (placed_sender, placed_receiver) = mpsc::channel::<PlacementResult>();
+
+        // companion file path > resolved date, shared by group
+        let group_dates: Mutex<HashMap<PathBuf, FileDateInfo>> = Mutex::new(HashMap::new());
+
+        thread::scope(|scope| {
+            if jobs > 1 {
+                let date_jobs = &date_job_receiver;
+                let placement_jobs = &placement_receiver;
+                let group_dates = &group_dates;
+
+                for _ in 0..jobs {
+                    let resolved_dates = resolved_date_sender.clone();
+                    scope.spawn(move ||
+                        resolve_dates(date_jobs, resolved_dates, group_dates,
+                                      no_exif_config, exif_config, reorganize_config)
+                    );
+
+                    let placed = placed_sender.clone();
+                    scope.spawn(move || place_files(placement_jobs, placed));
+                }
+            }
+
+            // workers stop when senders are dropped
+            drop(resolved_date_sender);
+            drop(placed_sender);
+            let date_job_sender = date_job_sender;
+
+            let placements = Placements {
+                jobs: if jobs > 1 { Some(placement_sender) } else { None },
+                results: &placed_receiver,
+                pending: 0,
+                placed_files: 0,
+                failed_files: Vec::new(),
+                has_errors: false
+            };
+
+            let report = ReorganizeReport {
+                files_total: 0,
+                placed_files: 0,
+                undated_files: Vec::new(),
+                failed_files: Vec::new(),
+                planned_files: Vec::new(),
+                collisions: Vec::new(),
+                duplicates: Vec::new(),
+                excluded_paths: 0,
+                traversal_errors: Vec::new(),
+                broken_links: Vec::new(),
+                left_in_place: Vec::new(),
+                stopped: false
+            };
+
+            let content_hashes = ContentHashes {
+                enabled: reorganize_config.dedupe,
+                by_path: HashMap::new(),
+                originals: HashMap::new()
+            };
+
+            let mut state = PlanState {
+                claimed_destinations: HashMap::new(), content_hashes, report, placements
+            };
+
+            let mut raw_groups = RawGroups::new(src_path);
+
+            // dates are resolved in any order, files are placed in order of discovery
+            let mut resolved_dates: BTreeMap<usize, ResolvedDate> = BTreeMap::new();
+
+            let mut queued_files: usize = 0;
+            let mut processed_files: usize = 0;
+            let mut walk_finished = false;
+
+            loop {
+                while !walk_finished && queued_files - processed_files < max_queued_files {
+                    match walker.next() {
+                        Some(Ok(file_path)) => {
+                            // destination inside source path, file has been placed by this run
+                            if state.claimed_destinations.contains_key(&file_path) {
+                                continue
                             }
-                        }
-                        Err(_) => {
-                            warn!("file '{}' doesn't contain EXIF meta-data", file_name);
-
-                            if no_exif_config.extract_dates_from_path {
-                                match reorganize_file_without_exif(
-                                    &file_path_str, dest_path,
-                                    file_name, no_exif_config
-                                ) {
-                                    Ok(_) => {}
-                                    Err(_) => has_errors = true
+
+                            info!("processing file '{}'", file_path.display());
+
+                            let group_file = if reorganize_config.group_raw {
+                                raw_groups.get_group_file(&file_path, file_filter)
+                            } else { None };
+
+                            let date_job = DateJob { index: queued_files, file_path, group_file };
+                            queued_files += 1;
+
+                            if jobs > 1 {
+                                if date_job_sender.send(date_job).is_err() {
+                                    return Err(Error::other("workers have stopped"))
                                 }
+
+                            } else {
+                                let resolved_date = resolve_date(date_job, &group_dates,
+                                                                 no_exif_config, exif_config,
+                                                                 reorganize_config);
+                                resolved_dates.insert(resolved_date.index, resolved_date);
                             }
                         }
+                        Some(Err(e)) => {
+                            error!("traversal error: {}", e);
+                            state.report.traversal_errors.push(e);
+                        }
+                        None => walk_finished = true
                     }
+                }
 
-                    on_progress(files.len(), index)
+                if processed_files == queued_files {
+                    break
                 }
 
-                if !has_errors {
-                    Ok(())
+                while !resolved_dates.contains_key(&processed_files) {
+                    match resolved_date_receiver.recv() {
+                        Ok(resolved_date) => {
+                            resolved_dates.insert(resolved_date.index, resolved_date);
+                        }
+                        Err(_) => return Err(Error::other("workers have stopped"))
+                    }
+                }
 
-                } else {
-                    Err(Error::from(ErrorKind::Other))
+                let resolved_date = resolved_dates.remove(&processed_files).unwrap();
+
+                plan_file(resolved_date, src_path, dest_path, reorganize_config, &mut state)?;
+
+                processed_files += 1;
+                on_progress(walker.discovered_files, processed_files);
+
+                if state.report.stopped {
+                    break
                 }
             }
-            Err(e) => {
-                error!("unable to get files: {}", e);
-                Err(e)
+
+            let PlanState { mut report, mut placements, .. } = state;
+
+            placements.wait_all();
+
+            report.files_total = walker.discovered_files;
+            report.excluded_paths = walker.excluded_paths;
+            report.broken_links = walker.broken_links;
+
+            report.placed_files = placements.placed_files;
+            report.failed_files = placements.failed_files;
+
+            for undated_file in report.undated_files.iter_mut() {
+                if report.failed_files.contains(&undated_file.src_file_path) {
+                    undated_file.unknown_file_path = None
+                }
+            }
+
+            if !placements.has_errors {
+                Ok(report)
+
+            } else {
+                Err(Error::other("workers have stopped"))
+            }
+        })
+    }
+
+    /// Choose destination for file and place it. Files are handled in order of discovery,
+    /// so collision decisions don't depend on workers count.
+    fn plan_file(resolved_date: ResolvedDate, src_path: &Path, dest_path: &Path,
+                 reorganize_config: &ReorganizeConfig,
+                 state: &mut PlanState) -> Result<(), io::Error> {
+        let file_path: &Path = &resolved_date.file_path;
+
+        let src_file_name = match file_path.file_name() {
+            Some(file_name) => file_name,
+            None => return Ok(())
+        };
+
+        let file_name = match reorganize_config.name_encoding {
+            Some(name_encoding) => decode_file_name(src_file_name, name_encoding),
+            None => src_file_name.to_os_string()
+        };
+
+        let destination = match get_file_destination(resolved_date.file_date_info, dest_path,
+                                                      &file_name, &reorganize_config.template,
+                                                      &reorganize_config.month_names,
+                                                      reorganize_config.with_milliseconds) {
+            Some(destination) => destination,
+            None => {
+                let relative_file_path = file_path.strip_prefix(src_path)
+                                                  .unwrap_or(Path::new(src_file_name));
+
+                return plan_undated_file(file_path, relative_file_path, resolved_date.content_hash,
+                                         reorganize_config, state)
             }
+        };
+
+        let PlanState { claimed_destinations, content_hashes, report, placements } = state;
+
+        info!("date source for '{}': {}", file_path.display(), destination.date_source);
+
+        let content_hash = resolved_date.content_hash;
+
+        if is_duplicate(file_path, content_hash, content_hashes, report, placements) {
+            leave_in_place(file_path, reorganize_config, report);
+            return Ok(())
+        }
+
+        // files planned earlier should be in destination before collision check
+        if claimed_destinations.contains_key(&destination.dest_file_path) ||
+            fs::symlink_metadata(&destination.dest_file_path).is_ok() {
+            placements.wait_all();
+        }
+
+        let resolution = resolve_destination(
+            file_path, &destination.dest_file_path,
+            claimed_destinations, content_hashes, reorganize_config,
+            &mut report.collisions
+        )?;
+
+        match resolution {
+            Resolution::Place(resolved) => {
+                add_original(file_path, &resolved.dest_file_path, content_hash, content_hashes);
+
+                claimed_destinations.insert(
+                    resolved.dest_file_path.clone(),
+                    file_path.to_path_buf()
+                );
+
+                if reorganize_config.dry_run {
+                    info!("dry-run: '{}' > '{}'", file_path.display(),
+                          resolved.dest_file_path.display());
+
+                    report.planned_files.push(PlannedFile {
+                        src_file_path: file_path.to_path_buf(),
+                        dest_file_path: resolved.dest_file_path,
+                        date_source: destination.date_source
+                    });
+
+                    placements.placed_files += 1;
+
+                } else {
+                    info!("{} '{}' > '{}'", reorganize_config.placement_mode,
+                          file_path.display(), resolved.dest_file_path.display());
+
+                    placements.add(PlacementJob {
+                        src_file_path: file_path.to_path_buf(),
+                        dest_path: destination.dest_path,
+                        dest_file_path: resolved.dest_file_path,
+                        overwrite: resolved.overwrite,
+                        placement_mode: reorganize_config.placement_mode
+                    });
+                }
+            }
+            Resolution::Skip => {}
+            Resolution::SkipIdentical => leave_in_place(file_path, reorganize_config, report),
+            Resolution::Stop => stop_processing(file_path, report, placements)
+        }
+
+        Ok(())
+    }
+
+    /// Check if file with the same content has been placed, duplicate is added to report.
+    fn is_duplicate(file_path: &Path, content_hash: Option<Hash>,
+                    content_hashes: &mut ContentHashes, report: &mut ReorganizeReport,
+                    placements: &mut Placements) -> bool {
+        let content_hash = match content_hash {
+            Some(content_hash) => content_hash,
+            None => return false
+        };
+
+        content_hashes.by_path.insert(file_path.to_path_buf(), content_hash);
+
+        if !content_hashes.originals.contains_key(&content_hash) {
+            return false
+        }
+
+        // original might be still in progress, it isn't a copy source if placement has failed
+        placements.wait_all();
+
+        let original_file_path = content_hashes.originals[&content_hash].clone();
+
+        if placements.failed_files.contains(&original_file_path) {
+            info!("original '{}' hasn't been placed, '{}' is used instead",
+                  original_file_path.display(), file_path.display());
+            content_hashes.originals.remove(&content_hash);
+            return false
+        }
+
+        info!("'{}' is duplicate of '{}', skip", file_path.display(), original_file_path.display());
+
+        report.duplicates.push(Duplicate {
+            src_file_path: file_path.to_path_buf(),
+            original_file_path
+        });
+
+        true
+    }
+
+    /// Register file which is placed (or planned in dry-run mode) as original for its content.
+    fn add_original(file_path: &Path, dest_file_path: &Path, content_hash: Option<Hash>,
+                    content_hashes: &mut ContentHashes) {
+        if let Some(content_hash) = content_hash {
+            content_hashes.originals.insert(content_hash, file_path.to_path_buf());
+            content_hashes.by_path.insert(dest_file_path.to_path_buf(), content_hash);
         }
     }
 
-    fn reorganize_file_without_exif(file_path_str: &str,
-                                    dest_path: &str, file_name: &str,
-                                    no_exif_config: &NoExifConfig) -> Result<(), io::Error> {
+    /// Source file isn't consumed in move mode, destination already has the same content.
+    fn leave_in_place(file_path: &Path, reorganize_config: &ReorganizeConfig,
+                      report: &mut ReorganizeReport) {
+        if reorganize_config.placement_mode == PlacementMode::Move {
+            warn!("'{}' has been left in place, destination has the same content", file_path.display());
+            report.left_in_place.push(file_path.to_path_buf());
+        }
+    }
+
+    /// Collision with `fail` policy: file isn't placed, other files aren't processed.
+    fn stop_processing(file_path: &Path, report: &mut ReorganizeReport,
+                       placements: &mut Placements) {
+        report.stopped = true;
+        placements.failed_files.push(file_path.to_path_buf());
+    }
+
+    /// Copy file without date to unknown directory, if it's set.
+    /// Path relative to source path is kept: unknown/2019/trip/IMG_0001.jpg
+    fn plan_undated_file(file_path: &Path, relative_file_path: &Path, content_hash: Option<Hash>,
+                         reorganize_config: &ReorganizeConfig,
+                         state: &mut PlanState) -> Result<(), io::Error> {
+        let PlanState { claimed_destinations, content_hashes, report, placements } = state;
+
+        let unknown_dir = match &reorganize_config.unknown_dir {
+            Some(unknown_dir) => unknown_dir,
+            None => {
+                warn!("unable to determine date for '{}', skip", file_path.display());
+
+                report.undated_files.push(UndatedFile {
+                    src_file_path: file_path.to_path_buf(), unknown_file_path: None
+                });
+
+                return Ok(())
+            }
+        };
+
+        if is_duplicate(file_path, content_hash, content_hashes, report, placements) {
+            return Ok(())
+        }
+
+        let unknown_file_path = unknown_dir.join(relative_file_path);
+
+        if claimed_destinations.contains_key(&unknown_file_path) ||
+            fs::symlink_metadata(&unknown_file_path).is_ok() {
+            placements.wait_all();
+        }
+
+        let resolution = resolve_destination(
+            file_path, &unknown_file_path,
+            claimed_destinations, content_hashes, reorganize_config,
+            &mut report.collisions
+        )?;
+
+        let resolved = match resolution {
+            Resolution::Place(resolved) => resolved,
+            Resolution::Skip | Resolution::SkipIdentical => {
+                report.undated_files.push(UndatedFile {
+                    src_file_path: file_path.to_path_buf(), unknown_file_path: None
+                });
+
+                return Ok(())
+            }
+            Resolution::Stop => {
+                stop_processing(file_path, report, placements);
+                return Ok(())
+            }
+        };
 
-        if no_exif_config.force_year {
-            let local_dt = Local.ymd(no_exif_config.year, 1, 1)
-                .and_hms_milli(9, 10, 11, 12);
-            let file_date = local_dt.naive_local().date();
+        add_original(file_path, &resolved.dest_file_path, content_hash, content_hashes);
+        claimed_destinations.insert(resolved.dest_file_path.clone(), file_path.to_path_buf());
 
-            let (result_path, result_file_path) = get_dest_path_and_filepath_with_date(
-                dest_path, file_name, &file_date
-            );
+        report.undated_files.push(UndatedFile {
+            src_file_path: file_path.to_path_buf(),
+            unknown_file_path: Some(resolved.dest_file_path.clone())
+        });
 
-            reorganize_file(file_date.year(), dest_path,
-                            &file_path_str, &result_path,
-                            &result_file_path)
+        if reorganize_config.dry_run {
+            info!("dry-run: '{}' > '{}' (unknown date)", file_path.display(),
+                  resolved.dest_file_path.display());
+            placements.placed_files += 1;
 
         } else {
-            let sanitized_path: String = get_path_without_dir_names(
-                file_path_str,
-                &no_exif_config.skip_dir_names_for_date_extract
-            );
+            info!("copy file without date '{}' > '{}'", file_path.display(),
+                  resolved.dest_file_path.display());
 
-            let extracted_dates = get_dates_from_path(&sanitized_path);
+            placements.add(PlacementJob {
+                src_file_path: file_path.to_path_buf(),
+                dest_path: resolved.dest_file_path.parent().unwrap_or(unknown_dir).to_path_buf(),
+                dest_file_path: resolved.dest_file_path,
+                overwrite: resolved.overwrite,
+                placement_mode: PlacementMode::Copy
+            });
+        }
+
+        Ok(())
+    }
+
+    fn resolve_dates(date_jobs: &Mutex<Receiver<DateJob>>, resolved_dates: Sender<ResolvedDate>,
+                     group_dates: &Mutex<HashMap<PathBuf, FileDateInfo>>,
+                     no_exif_config: &NoExifConfig, exif_config: &ExifConfig,
+                     reorganize_config: &ReorganizeConfig) {
+        loop {
+            let date_job = match date_jobs.lock().unwrap().recv() {
+                Ok(date_job) => date_job,
+                Err(_) => break
+            };
+
+            let resolved_date = resolve_date(date_job, group_dates,
+                                             no_exif_config, exif_config, reorganize_config);
+
+            if resolved_dates.send(resolved_date).is_err() {
+                break
+            }
+        }
+    }
 
-            if !extracted_dates.is_empty() {
-                let file_date = extracted_dates.last().unwrap();
-                let (result_path, result_file_path) = get_dest_path_and_filepath_with_date(
-                    dest_path, file_name, file_date
+    fn resolve_date(date_job: DateJob, group_dates: &Mutex<HashMap<PathBuf, FileDateInfo>>,
+                    no_exif_config: &NoExifConfig, exif_config: &ExifConfig,
+                    reorganize_config: &ReorganizeConfig) -> ResolvedDate {
+        let file_path: &Path = &date_job.file_path;
+
+        let file_date_info = match &date_job.group_file {
+            Some(group_file_path) => {
+                let group_date_info = get_group_date(
+                    group_file_path, group_dates, no_exif_config, exif_config
                 );
 
-                reorganize_file(file_date.year(), dest_path,
-                                &file_path_str, &result_path,
-                                &result_file_path)
+                if group_file_path == file_path {
+                    group_date_info
 
-            } else {
-                info!("unable to reorganize file because file path doesn't \
-                   contain any information about date");
+                } else if group_date_info.file_date.is_some() {
+                    info!("date of '{}' has been used for '{}'", group_file_path.display(),
+                          file_path.display());
+                    group_date_info
 
-                Ok(())
+                } else {
+                    resolve_file_date(file_path, no_exif_config, exif_config)
+                }
+            }
+            None => resolve_file_date(file_path, no_exif_config, exif_config)
+        };
+
+        // files without date are placed only to unknown directory
+        let placeable = file_date_info.file_date.is_some() || reorganize_config.unknown_dir.is_some();
+
+        let content_hash = if reorganize_config.dedupe && placeable {
+            match get_content_hash(file_path) {
+                Ok(content_hash) => Some(content_hash),
+                Err(e) => {
+                    warn!("unable to get content hash for '{}': {}", file_path.display(), e);
+                    None
+                }
             }
+
+        } else { None };
+
+        ResolvedDate {
+            index: date_job.index, file_path: date_job.file_path, file_date_info, content_hash
         }
     }
 
-    fn create_year_dir_if_not_exists(output_path: &str, year: i32) -> Result<(), io::Error> {
-        let dir_name = format!("{}/{}", output_path, year);
-        let target_path = Path::new(&dir_name);
+    fn place_files(placement_jobs: &Mutex<Receiver<PlacementJob>>,
+                   placed: Sender<PlacementResult>) {
+        loop {
+            let placement_job = match placement_jobs.lock().unwrap().recv() {
+                Ok(placement_job) => placement_job,
+                Err(_) => break
+            };
 
-        if !target_path.exists() {
-            fs::create_dir_all(target_path)?
+            if placed.send(place_file(placement_job)).is_err() {
+                break
+            }
         }
+    }
 
-        Ok(())
+    fn place_file(placement_job: PlacementJob) -> PlacementResult {
+        let placed = reorganize_file(&placement_job.src_file_path, &placement_job.dest_path,
+                                     &placement_job.dest_file_path, placement_job.overwrite,
+                                     placement_job.placement_mode).is_ok();
+
+        PlacementResult { src_file_path: placement_job.src_file_path, placed }
     }
 
-    fn get_dest_path_and_filepath_with_datetime(root_dest_path: &str, original_file_name: &str,
-                                                file_datetime: NaiveDateTime) -> (String, String) {
-        let result_datetime_format = file_datetime.format(DEST_DATETIME_FORMAT);
+    /// Apply collision policy if destination file already exists.
+    fn resolve_destination(src_file_path: &Path, dest_file_path: &Path,
+                           claimed_destinations: &HashMap<PathBuf, PathBuf>,
+                           content_hashes: &mut ContentHashes,
+                           reorganize_config: &ReorganizeConfig,
+                           collisions: &mut Vec<Collision>)
+                                            -> Result<Resolution, io::Error> {
+        let existing_file_path = match get_existing_file_path(
+            dest_file_path, claimed_destinations, reorganize_config.dry_run
+        ) {
+            Some(existing_file_path) => existing_file_path,
+            None => return Ok(
+                Resolution::Place(ResolvedDestination {
+                    dest_file_path: dest_file_path.to_path_buf(), overwrite: false
+                })
+            )
+        };
+
+        info!("destination file '{}' already exists", dest_file_path.display());
+
+        let (resolution, decision) = if content_hashes.is_same_content(
+            src_file_path, &existing_file_path
+        ) {
+            (Resolution::SkipIdentical, CollisionDecision::SkippedIdentical)
+
+        } else {
+            match reorganize_config.on_conflict {
+                ConflictPolicy::Skip => (Resolution::Skip, CollisionDecision::Skipped),
+                ConflictPolicy::Overwrite => (
+                    Resolution::Place(ResolvedDestination {
+                        dest_file_path: dest_file_path.to_path_buf(), overwrite: true
+                    }),
+                    CollisionDecision::Overwritten
+                ),
+                ConflictPolicy::Rename => {
+                    match get_free_file_path(
+                        src_file_path, dest_file_path,
+                        claimed_destinations, content_hashes, reorganize_config.dry_run
+                    ) {
+                        Some(new_file_path) => (
+                            Resolution::Place(ResolvedDestination {
+                                dest_file_path: new_file_path.clone(), overwrite: false
+                            }),
+                            CollisionDecision::Renamed(new_file_path)
+                        ),
+                        None => (Resolution::SkipIdentical, CollisionDecision::SkippedIdentical)
+                    }
+                }
+                ConflictPolicy::Fail => (Resolution::Stop, CollisionDecision::Failed),
+                ConflictPolicy::KeepLarger => {
+                    let src_file_size = fs::metadata(src_file_path)?.len();
+                    let existing_file_size = fs::metadata(&existing_file_path)?.len();
 
-        let result_filename = format!("{}__{}", result_datetime_format, original_file_name);
+                    if src_file_size > existing_file_size {
+                        (
+                            Resolution::Place(ResolvedDestination {
+                                dest_file_path: dest_file_path.to_path_buf(), overwrite: true
+                            }),
+                            CollisionDecision::ReplacedWithLarger
+                        )
 
-        info!("result filename: '{}'", result_filename);
+                    } else {
+                        (Resolution::Skip, CollisionDecision::KeptExistingLarger)
+                    }
+                }
+            }
+        };
 
-        let month_name = get_month_name(file_datetime.month());
+        info!("collision decision: {}", decision);
 
-        let result_path = format!("{}/{}/{}", root_dest_path, file_datetime.year(), month_name);
-        info!("result_path: '{}'", result_path);
+        if let CollisionDecision::Failed = decision {
+            error!("destination file '{}' already exists, stop", dest_file_path.display());
+        }
 
-        let result_file_path = format!("{}/{}", &result_path, result_filename);
+        collisions.push(Collision {
+            src_file_path: src_file_path.to_path_buf(),
+            dest_file_path: dest_file_path.to_path_buf(),
+            decision
+        });
 
-        (result_path, result_file_path)
+        Ok(resolution)
     }
 
-    fn get_dest_path_and_filepath_with_date(root_dest_path: &str, original_file_name: &str,
-                                            file_datetime: &NaiveDate) -> (String, String) {
-        let result_datetime_format = file_datetime.format(DEST_DATE_FORMAT);
+    /// Returns path of file which occupies destination. In dry-run mode destination might be
+    /// occupied by file planned earlier, so its source file is returned.
+    fn get_existing_file_path(dest_file_path: &Path,
+                              claimed_destinations: &HashMap<PathBuf, PathBuf>,
+                              dry_run: bool) -> Option<PathBuf> {
+        if dry_run {
+            if let Some(src_file_path) = claimed_destinations.get(dest_file_path) {
+                return Some(src_file_path.clone())
+            }
+        }
 
-        let result_filename = format!("{}__{}", result_datetime_format, original_file_name);
+        if fs::symlink_metadata(dest_file_path).is_ok() {
+            Some(dest_file_path.to_path_buf())
 
-        info!("result filename: '{}'", result_filename);
+        } else { None }
+    }
 
-        let month_name = get_month_name(file_datetime.month());
+    fn is_same_content(src_file_path: &Path, existing_file_path: &Path) -> bool {
+        match files_are_equal(src_file_path, existing_file_path) {
+            Ok(equal) => equal,
+            Err(e) => {
+                warn!("unable to compare '{}' with '{}': {}",
+                      src_file_path.display(), existing_file_path.display(), e);
+                false
+            }
+        }
+    }
+
+    /// Append numeric suffix to file name until free path is found.
+    /// Example: 2019-07-13__IMG_0001.jpg > 2019-07-13__IMG_0001_1.jpg
+    ///
+    /// Returns `None` if source file has been already placed with one of suffixes.
+    fn get_free_file_path(src_file_path: &Path, dest_file_path: &Path,
+                          claimed_destinations: &HashMap<PathBuf, PathBuf>,
+                          content_hashes: &mut ContentHashes,
+                          dry_run: bool) -> Option<PathBuf> {
+        let parent = dest_file_path.parent().unwrap_or(Path::new(""));
+        let file_stem = dest_file_path.file_stem().unwrap_or_default();
+        let extension = dest_file_path.extension();
+
+        let mut index = 1;
 
-        let result_path = format!("{}/{}/{}", root_dest_path, file_datetime.year(), month_name);
-        info!("result_path: '{}'", result_path);
+        loop {
+            let mut file_name = OsString::from(file_stem);
+            file_name.push(format!("_{}", index));
 
-        let result_file_path = format!("{}/{}", &result_path, result_filename);
+            if let Some(extension) = extension {
+                file_name.push(".");
+                file_name.push(extension);
+            }
+
+            let candidate = parent.join(file_name);
 
-        (result_path, result_file_path)
+            match get_existing_file_path(&candidate, claimed_destinations, dry_run) {
+                Some(existing_file_path) => {
+                    if content_hashes.is_same_content(src_file_path, &existing_file_path) {
+                        return None
+                    }
+                }
+                None => {
+                    if !claimed_destinations.contains_key(&candidate) {
+                        return Some(candidate)
+                    }
+                }
+            }
+
+            index += 1;
+        }
     }
 
-    fn reorganize_file(year: i32, root_dest_path: &str, src_file_path: &str,
-                       dest_path: &str, dest_file_path: &str) -> Result<(), io::Error> {
-        create_year_dir_if_not_exists(root_dest_path, year)?;
+    /// Date of file group (RAW + JPEG), resolved once by companion file.
+    /// Lock is held during resolution, so companion file isn't read after it has been moved.
+    fn get_group_date(companion_file_path: &Path,
+                      group_dates: &Mutex<HashMap<PathBuf, FileDateInfo>>,
+                      no_exif_config: &NoExifConfig,
+                      exif_config: &ExifConfig) -> FileDateInfo {
+        let mut group_dates = group_dates.lock().unwrap();
+
+        group_dates.entry(companion_file_path.to_path_buf())
+            .or_insert_with(|| resolve_file_date(companion_file_path, no_exif_config, exif_config))
+            .clone()
+    }
+
+    /// Get destination for file by resolved date.
+    /// Returns `None` if date can't be determined.
+    fn get_file_destination(file_date_info: FileDateInfo, dest_path: &Path, file_name: &OsStr,
+                            template: &PathTemplate, month_names: &MonthNames,
+                            with_milliseconds: bool) -> Option<FileDestination> {
+        let file_date = file_date_info.file_date?;
+
+        let relative_file_path = get_relative_file_path(
+            &file_date, file_date_info.camera_model.as_deref(), file_name,
+            template, month_names, with_milliseconds
+        );
+
+        let result_file_path = dest_path.join(relative_file_path);
+        info!("result file path: '{}'", result_file_path.display());
 
-        match fs::create_dir_all(&dest_path) {
+        let result_path = result_file_path.parent().unwrap_or(dest_path).to_path_buf();
+        info!("result_path: '{}'", result_path.display());
+
+        Some(
+            FileDestination {
+                dest_path: result_path,
+                dest_file_path: result_file_path,
+                date_source: file_date.source
+            }
+        )
+    }
+
+    /// Destination file path relative to destination directory.
+    /// Dates from filesystem timestamps are marked with suffix: 2019-07-13__IMG_0001__mtime.jpg
+    pub fn get_relative_file_path(file_date: &FileDate, camera_model: Option<&str>,
+                                  file_name: &OsStr, template: &PathTemplate,
+                                  month_names: &MonthNames, with_milliseconds: bool) -> OsString {
+        let month_name = month_names.get_month_name(file_date.date.month());
+
+        let relative_file_path = template.render(&TemplateValues {
+            date: file_date.date,
+            time: file_date.time,
+            month_name: &month_name,
+            camera_model,
+            original_name: file_name,
+            date_source: file_date.source,
+            with_milliseconds
+        });
+
+        match file_date.source {
+            DateSource::FileTime(timestamp) =>
+                append_name_suffix(&relative_file_path, &format!("__{}", timestamp)),
+            _ => relative_file_path
+        }
+    }
+
+    /// Insert suffix before file extension.
+    fn append_name_suffix(file_path: &OsStr, suffix: &str) -> OsString {
+        let file_path = Path::new(file_path);
+
+        let mut file_name = file_path.file_stem().unwrap_or_default().to_os_string();
+        file_name.push(suffix);
+
+        if let Some(extension) = file_path.extension() {
+            file_name.push(".");
+            file_name.push(extension);
+        }
+
+        match file_path.parent() {
+            Some(parent) => parent.join(file_name).into_os_string(),
+            None => file_name
+        }
+    }
+
+    fn reorganize_file(src_file_path: &Path, dest_path: &Path, dest_file_path: &Path,
+                       overwrite: bool, placement_mode: PlacementMode) -> Result<(), io::Error> {
+        match fs::create_dir_all(dest_path) {
             Ok(_) => {
-                info!("copy '{}' > '{}'", &src_file_path, &dest_file_path);
+                let result = if overwrite {
+                    replace_file(src_file_path, dest_file_path, placement_mode)
+
+                } else {
+                    place_file_to(src_file_path, dest_file_path, placement_mode)
+                };
 
-                match fs::copy(&src_file_path, &dest_file_path) {
+                match result {
                     Ok(_) => {
-                        info!("file has been copied");
+                        debug!("file '{}' has been placed", dest_file_path.display());
                         Ok(())
                     }
                     Err(e) => {
-                        error!("unable to copy file to destination: {}", e);
+                        error!("unable to {} '{}' to destination: {}",
+                               placement_mode, src_file_path.display(), e);
                         Err(e)
                     }
                 }
             }
             Err(e) => {
-                error!("unable to create path '{}': {}", &dest_path, e);
+                error!("unable to create path '{}': {}", dest_path.display(), e);
                 Err(e)
             }
         }
     }
 
-    fn get_month_name(month_index: u32) -> String {
-        match month_index {
-            1 => String::from(JANUARY),
-            2 => String::from(FEBRUARY),
-            3 => String::from(MARCH),
-            4 => String::from(APRIL),
-            5 => String::from(MAY),
-            6 => String::from(JUNE),
-            7 => String::from(JULY),
-            8 => String::from(AUGUST),
-            9 => String::from(SEPTEMBER),
-            10 => String::from(OCTOBER),
-            11 => String::from(NOVEMBER),
-            12 => String::from(DECEMBER),
-            _ => String::from(UNKNOWN_MONTH_NAME)
+    fn place_file_to(src_file_path: &Path, dest_file_path: &Path,
+                     placement_mode: PlacementMode) -> Result<(), io::Error> {
+        match placement_mode {
+            PlacementMode::Copy => fs::copy(src_file_path, dest_file_path).map(|_| ()),
+            PlacementMode::Move => move_file(src_file_path, dest_file_path),
+            PlacementMode::HardLink => fs::hard_link(src_file_path, dest_file_path),
+            PlacementMode::SymLink => create_symlink(src_file_path, dest_file_path),
+            PlacementMode::Reflink => reflink_file(src_file_path, dest_file_path)
+        }
+    }
+
+    /// Place file next to existing one under temporary name, then rename it over existing file.
+    /// Existing file is kept if placement fails.
+    fn replace_file(src_file_path: &Path, dest_file_path: &Path,
+                    placement_mode: PlacementMode) -> Result<(), io::Error> {
+        let temp_file_path = get_temp_file_path(dest_file_path);
+
+        if fs::symlink_metadata(&temp_file_path).is_ok() {
+            info!("remove stale temporary file '{}'", temp_file_path.display());
+            fs::remove_file(&temp_file_path)?;
         }
+
+        place_file_to(src_file_path, &temp_file_path, placement_mode)?;
+
+        match fs::rename(&temp_file_path, dest_file_path) {
+            Ok(_) => {
+                info!("existing file '{}' has been replaced", dest_file_path.display());
+                Ok(())
+            }
+            Err(e) => {
+                error!("unable to replace '{}': {}", dest_file_path.display(), e);
+
+                let cleanup_result = match placement_mode {
+                    PlacementMode::Move => move_file(&temp_file_path, src_file_path),
+                    _ => fs::remove_file(&temp_file_path)
+                };
+
+                if let Err(cleanup_error) = cleanup_result {
+                    error!("unable to clean up temporary file '{}': {}",
+                           temp_file_path.display(), cleanup_error);
+                }
+
+                Err(e)
+            }
+        }
+    }
+
+    /// Hidden file in the same directory: .2019-07-13__IMG_0001.jpg.imgtag-tmp
+    fn get_temp_file_path(dest_file_path: &Path) -> PathBuf {
+        let mut file_name = OsString::from(".");
+        file_name.push(dest_file_path.file_name().unwrap_or_default());
+        file_name.push(".imgtag-tmp");
+
+        dest_file_path.with_file_name(file_name)
     }
 }
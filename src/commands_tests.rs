@@ -1,11 +1,20 @@
 #[cfg(test)]
 pub mod commands_tests {
-    use crate::commands::commands::reorganize_files;
-    use std::fs;
-    use std::path::Path;
+    use crate::commands::commands::{reorganize_files, CollisionDecision, ReorganizeReport, PlannedFile};
+    use std::{env, fs};
+    use std::fs::File;
+    use std::time::{Duration, UNIX_EPOCH};
+    use std::path::{Path, PathBuf};
     use crate::logging::logging::get_logging_config;
-    use crate::domain::domain::NoExifConfig;
+    use crate::domain::domain::{FileFilter, NoExifConfig, ReorganizeConfig, DateSource, PlacementMode,
+                                ConflictPolicy, ExifConfig, ExifDateTag,
+                                TargetTimeZone, FileTimestamp, PathDatePattern};
+    use crate::exif::exif::DEFAULT_DATE_TAGS;
     use crate::get_extension_filters;
+    use crate::template::template::{parse_template, DEFAULT_TEMPLATE};
+    use crate::locale::locale::{get_month_names, DEFAULT_LOCALE};
+    use crate::exclusions::exclusions::{ExcludeRules, parse_exclude_patterns};
+    use crate::time_shift::time_shift::{TimeShiftConfig, parse_time_shift_rules};
 
     const SOURCE_DIR_NAME: &str = "img-src";
     const RESULTS_DIR_NAME: &str = "results";
@@ -16,17 +25,13 @@ pub mod commands_tests {
     fn date_should_extracted_from_path_for_files_without_exif() {
         remove_results_dir();
 
-        let no_exif_config = NoExifConfig {
-            extract_dates_from_path: true,
-            skip_dir_names_for_date_extract: Vec::new(),
-            force_year: false,
-            year: 0
-        };
+        let no_exif_config = get_no_exif_config(true);
 
-        let ext_filters = get_extension_filters();
+        let file_filter = get_file_filter();
 
-        match reorganize_files(SOURCE_DIR_NAME, RESULTS_DIR_NAME,
-                               &ext_filters, &no_exif_config, show_progress) {
+        match reorganize_files(Path::new(SOURCE_DIR_NAME), Path::new(RESULTS_DIR_NAME),
+                               &file_filter, &no_exif_config,
+                               &get_exif_config(), &get_reorganize_config(), show_progress) {
             Ok(_) => {}
             Err(_) => {}
         }
@@ -41,17 +46,13 @@ pub mod commands_tests {
     fn result_filename_should_be_stored_in_year_directory() {
         remove_results_dir();
 
-        let no_exif_config = NoExifConfig {
-            extract_dates_from_path: false,
-            skip_dir_names_for_date_extract: Vec::new(),
-            force_year: false,
-            year: 0
-        };
+        let no_exif_config = get_no_exif_config(false);
 
-        let ext_filters = get_extension_filters();
+        let file_filter = get_file_filter();
 
-        match reorganize_files(SOURCE_DIR_NAME, RESULTS_DIR_NAME,
-                               &ext_filters, &no_exif_config, show_progress) {
+        match reorganize_files(Path::new(SOURCE_DIR_NAME), Path::new(RESULTS_DIR_NAME),
+                               &file_filter, &no_exif_config,
+                               &get_exif_config(), &get_reorganize_config(), show_progress) {
             Ok(_) => {}
             Err(_) => {}
         }
@@ -69,17 +70,13 @@ pub mod commands_tests {
 
         remove_results_dir();
 
-        let no_exif_config = NoExifConfig {
-            extract_dates_from_path: false,
-            skip_dir_names_for_date_extract: Vec::new(),
-            force_year: false,
-            year: 0
-        };
+        let no_exif_config = get_no_exif_config(false);
 
-        let ext_filters = get_extension_filters();
+        let file_filter = get_file_filter();
 
-        match reorganize_files(SOURCE_DIR_NAME, RESULTS_DIR_NAME,
-                               &ext_filters, &no_exif_config, show_progress) {
+        match reorganize_files(Path::new(SOURCE_DIR_NAME), Path::new(RESULTS_DIR_NAME),
+                               &file_filter, &no_exif_config,
+                               &get_exif_config(), &get_reorganize_config(), show_progress) {
             Ok(_) => {}
             Err(_) => {}
         }
@@ -90,6 +87,986 @@ pub mod commands_tests {
         assert!(!expected_result_file.exists());
     }
 
+    #[test]
+    fn dry_run_should_not_modify_filesystem() {
+        let test_dir = get_test_dir("dry_run_should_not_modify_filesystem");
+        let results_dir = test_dir.join("results");
+
+        let no_exif_config = get_no_exif_config(true);
+
+        let mut reorganize_config = get_reorganize_config();
+        reorganize_config.dry_run = true;
+
+        let file_filter = get_file_filter();
+
+        let report = reorganize_files(Path::new(SOURCE_DIR_NAME), &results_dir,
+                                      &file_filter, &no_exif_config,
+                                      &get_exif_config(), &reorganize_config, show_progress).unwrap();
+
+        assert!(!results_dir.exists());
+
+        let expected_dest_file_path = results_dir.join("2020/Октябрь/2020-10-10__12-09-47__IMG_20201010_120947.jpg");
+        let planned_file = assert_planned(&report, &expected_dest_file_path);
+        assert_eq!(planned_file.date_source, DateSource::Exif(ExifDateTag::DateTimeOriginal));
+
+        let expected_dest_file_path = results_dir.join(EXPECTED_FILE_WITHOUT_DATE_IN_EXIF.trim_start_matches('/'));
+        let planned_file = assert_planned(&report, &expected_dest_file_path);
+        assert_eq!(planned_file.date_source, DateSource::Path(PathDatePattern::Solid));
+
+        fs::remove_dir_all(&test_dir).unwrap();
+    }
+
+    #[test]
+    fn source_file_should_be_removed_in_move_mode() {
+        let test_dir = get_test_dir("source_file_should_be_removed_in_move_mode");
+        let src_dir = test_dir.join("src");
+        let results_dir = test_dir.join("results");
+
+        let src_file_path = src_dir.join("IMG_20201010_120947.jpg");
+
+        fs::create_dir_all(&src_dir).unwrap();
+        fs::copy(format!("{}/20201010/IMG_20201010_120947.jpg", SOURCE_DIR_NAME),
+                 &src_file_path).unwrap();
+
+        let no_exif_config = get_no_exif_config(false);
+
+        let mut reorganize_config = get_reorganize_config();
+        reorganize_config.placement_mode = PlacementMode::Move;
+
+        let file_filter = get_file_filter();
+
+        reorganize_files(&src_dir, &results_dir,
+                         &file_filter, &no_exif_config,
+                         &get_exif_config(), &reorganize_config, show_progress).unwrap();
+
+        let expected_result_filename = results_dir.join("2020/Октябрь/2020-10-10__12-09-47__IMG_20201010_120947.jpg");
+
+        assert!(expected_result_filename.exists());
+        assert!(!src_file_path.exists());
+
+        fs::remove_dir_all(&test_dir).unwrap();
+    }
+
+    #[test]
+    fn hard_link_should_be_created_in_hard_link_mode() {
+        let test_dir = get_test_dir("hard_link_should_be_created_in_hard_link_mode");
+        let src_dir = test_dir.join("src");
+        let results_dir = test_dir.join("results");
+
+        // links can't be created across filesystems
+        let src_file_path = src_dir.join("IMG_20201010_120947.jpg");
+
+        fs::create_dir_all(&src_dir).unwrap();
+        fs::copy(format!("{}/20201010/IMG_20201010_120947.jpg", SOURCE_DIR_NAME),
+                 &src_file_path).unwrap();
+
+        let no_exif_config = get_no_exif_config(false);
+
+        let mut reorganize_config = get_reorganize_config();
+        reorganize_config.placement_mode = PlacementMode::HardLink;
+
+        let file_filter = get_file_filter();
+
+        reorganize_files(&src_dir, &results_dir,
+                         &file_filter, &no_exif_config,
+                         &get_exif_config(), &reorganize_config, show_progress).unwrap();
+
+        let expected_result_filename = results_dir.join("2020/Октябрь/2020-10-10__12-09-47__IMG_20201010_120947.jpg");
+
+        assert!(src_file_path.exists());
+        assert!(expected_result_filename.exists());
+
+        #[cfg(unix)]
+        {
+            use std::os::unix::fs::MetadataExt;
+            assert!(fs::metadata(&expected_result_filename).unwrap().nlink() > 1);
+        }
+
+        fs::remove_dir_all(&test_dir).unwrap();
+    }
+
+    #[test]
+    fn different_file_with_same_destination_should_be_renamed() {
+        let test_dir = get_test_dir("different_file_with_same_destination_should_be_renamed");
+        let results_dir = test_dir.join("results");
+
+        let dest_file_path = results_dir.join("2020/Октябрь/2020-10-10__12-09-47__IMG_20201010_120947.jpg");
+        fs::create_dir_all(results_dir.join("2020/Октябрь")).unwrap();
+        fs::write(&dest_file_path, "other file").unwrap();
+
+        let no_exif_config = get_no_exif_config(false);
+
+        let file_filter = get_file_filter();
+
+        let report = reorganize_files(Path::new(SOURCE_DIR_NAME), &results_dir,
+                                      &file_filter, &no_exif_config,
+                                      &get_exif_config(), &get_reorganize_config(),
+                                      show_progress).unwrap();
+
+        let expected_result_filename = results_dir.join("2020/Октябрь/2020-10-10__12-09-47__IMG_20201010_120947_1.jpg");
+
+        assert!(expected_result_filename.exists());
+        assert_eq!(fs::read_to_string(&dest_file_path).unwrap(), "other file");
+        assert_eq!(report.collisions.len(), 1);
+
+        let report = reorganize_files(Path::new(SOURCE_DIR_NAME), &results_dir,
+                                      &file_filter, &no_exif_config,
+                                      &get_exif_config(), &get_reorganize_config(),
+                                      show_progress).unwrap();
+
+        match report.collisions.first().unwrap().decision {
+            CollisionDecision::SkippedIdentical => {}
+            _ => panic!("identical file should be skipped")
+        }
+
+        fs::remove_dir_all(&test_dir).unwrap();
+    }
+
+    #[test]
+    fn existing_file_should_be_replaced_on_overwrite_policy() {
+        let test_dir = get_test_dir("existing_file_should_be_replaced_on_overwrite_policy");
+        let src_dir = test_dir.join("src");
+        let results_dir = test_dir.join("results");
+
+        let src_file_path = src_dir.join("IMG_20201010_120947.jpg");
+
+        fs::create_dir_all(&src_dir).unwrap();
+        fs::copy(format!("{}/20201010/IMG_20201010_120947.jpg", SOURCE_DIR_NAME),
+                 &src_file_path).unwrap();
+
+        let dest_dir = results_dir.join("2020/Октябрь");
+        let dest_file_path = dest_dir.join("2020-10-10__12-09-47__IMG_20201010_120947.jpg");
+        fs::create_dir_all(&dest_dir).unwrap();
+        fs::write(&dest_file_path, "other file").unwrap();
+
+        let mut reorganize_config = get_reorganize_config();
+        reorganize_config.on_conflict = ConflictPolicy::Overwrite;
+
+        let report = reorganize_files(&src_dir, &results_dir,
+                                      &get_file_filter(), &get_no_exif_config(false),
+                                      &get_exif_config(), &reorganize_config, show_progress).unwrap();
+
+        assert_eq!(report.placed_files, 1);
+        assert_eq!(fs::read(&dest_file_path).unwrap(), fs::read(&src_file_path).unwrap());
+
+        // temporary file has been renamed over existing one
+        assert_eq!(fs::read_dir(&dest_dir).unwrap().count(), 1);
+
+        fs::remove_dir_all(&test_dir).unwrap();
+    }
+
+    #[test]
+    fn identical_source_should_be_reported_as_left_in_place_in_move_mode() {
+        let test_dir = get_test_dir("identical_source_should_be_reported_as_left_in_place_in_move_mode");
+        let src_dir = test_dir.join("src");
+        let results_dir = test_dir.join("results");
+
+        let src_file_path = src_dir.join("IMG_20201010_120947.jpg");
+
+        fs::create_dir_all(&src_dir).unwrap();
+        fs::copy(format!("{}/20201010/IMG_20201010_120947.jpg", SOURCE_DIR_NAME),
+                 &src_file_path).unwrap();
+
+        let dest_dir = results_dir.join("2020/Октябрь");
+        fs::create_dir_all(&dest_dir).unwrap();
+        fs::copy(&src_file_path, dest_dir.join("2020-10-10__12-09-47__IMG_20201010_120947.jpg")).unwrap();
+
+        let mut reorganize_config = get_reorganize_config();
+        reorganize_config.placement_mode = PlacementMode::Move;
+
+        let report = reorganize_files(&src_dir, &results_dir,
+                                      &get_file_filter(), &get_no_exif_config(false),
+                                      &get_exif_config(), &reorganize_config, show_progress).unwrap();
+
+        assert_eq!(report.placed_files, 0);
+        assert_eq!(report.left_in_place, vec![src_file_path.clone()]);
+        assert!(src_file_path.exists());
+
+        fs::remove_dir_all(&test_dir).unwrap();
+    }
+
+    #[test]
+    fn processing_should_be_stopped_with_report_on_fail_policy() {
+        let test_dir = get_test_dir("processing_should_be_stopped_with_report_on_fail_policy");
+        let src_dir = test_dir.join("src");
+        let results_dir = test_dir.join("results");
+
+        fs::create_dir_all(src_dir.join("a")).unwrap();
+        fs::create_dir_all(src_dir.join("b")).unwrap();
+        fs::copy(format!("{}/20201010/IMG_20201010_120947.jpg", SOURCE_DIR_NAME),
+                 src_dir.join("a/IMG_0001.jpg")).unwrap();
+        fs::copy(format!("{}/20201010/IMG_20201010_120947.jpg", SOURCE_DIR_NAME),
+                 src_dir.join("b/IMG_0001.jpg")).unwrap();
+
+        // same date and name, other content
+        let mut contents = fs::read(src_dir.join("b/IMG_0001.jpg")).unwrap();
+        contents.push(0);
+        fs::write(src_dir.join("b/IMG_0001.jpg"), contents).unwrap();
+
+        let no_exif_config = get_no_exif_config(false);
+
+        let mut reorganize_config = get_reorganize_config();
+        reorganize_config.on_conflict = ConflictPolicy::Fail;
+
+        let report = reorganize_files(&src_dir, &results_dir,
+                                      &get_file_filter(), &no_exif_config,
+                                      &get_exif_config(), &reorganize_config, show_progress).unwrap();
+
+        assert!(report.stopped);
+        assert_eq!(report.placed_files, 1);
+        assert_eq!(report.failed_files.len(), 1);
+
+        match report.collisions.first().unwrap().decision {
+            CollisionDecision::Failed => {}
+            _ => panic!("collision should be failed")
+        }
+
+        fs::remove_dir_all(&test_dir).unwrap();
+    }
+
+    #[test]
+    fn destination_path_should_be_built_with_template() {
+        let test_dir = get_test_dir("destination_path_should_be_built_with_template");
+        let results_dir = test_dir.join("results");
+
+        let no_exif_config = get_no_exif_config(true);
+
+        let mut reorganize_config = get_reorganize_config();
+        reorganize_config.dry_run = true;
+        reorganize_config.template = parse_template("{year}/{month}/{day}/{original_name}").unwrap();
+
+        let file_filter = get_file_filter();
+
+        let report = reorganize_files(Path::new(SOURCE_DIR_NAME), &results_dir,
+                                      &file_filter, &no_exif_config,
+                                      &get_exif_config(), &reorganize_config, show_progress).unwrap();
+
+        let expected_dest_file_paths = [
+            results_dir.join("2020/10/10/IMG_20201010_120947.jpg"),
+            results_dir.join("2013/05/17/manga__berserk__forest.jpg")
+        ];
+
+        for expected_dest_file_path in expected_dest_file_paths.iter() {
+            assert_planned(&report, expected_dest_file_path);
+        }
+
+        fs::remove_dir_all(&test_dir).unwrap();
+    }
+
+    #[test]
+    fn time_should_be_converted_to_utc_with_milliseconds() {
+        let test_dir = get_test_dir("time_should_be_converted_to_utc_with_milliseconds");
+        let results_dir = test_dir.join("results");
+
+        let no_exif_config = get_no_exif_config(false);
+
+        let mut exif_config = get_exif_config();
+        exif_config.timezone = Some(TargetTimeZone::Utc);
+
+        let mut reorganize_config = get_reorganize_config();
+        reorganize_config.dry_run = true;
+        reorganize_config.with_milliseconds = true;
+
+        let file_filter = get_file_filter();
+
+        let report = reorganize_files(Path::new(SOURCE_DIR_NAME), &results_dir,
+                                      &file_filter, &no_exif_config,
+                                      &exif_config, &reorganize_config, show_progress).unwrap();
+
+        let expected_dest_file_path = results_dir.join("2020/Октябрь/2020-10-10__09-09-47-678__IMG_20201010_120947.jpg");
+
+        assert_planned(&report, &expected_dest_file_path);
+
+        fs::remove_dir_all(&test_dir).unwrap();
+    }
+
+    #[test]
+    fn time_shift_rule_should_be_applied_for_camera_model() {
+        let test_dir = get_test_dir("time_shift_rule_should_be_applied_for_camera_model");
+        let results_dir = test_dir.join("results");
+
+        let no_exif_config = get_no_exif_config(false);
+
+        let mut exif_config = get_exif_config();
+        exif_config.time_shift.rules = parse_time_shift_rules(
+            "model:pixel 2 xl = -01:00:00\ndir:other = +05:00"
+        ).unwrap();
+
+        let mut reorganize_config = get_reorganize_config();
+        reorganize_config.dry_run = true;
+
+        let file_filter = get_file_filter();
+
+        let report = reorganize_files(Path::new(SOURCE_DIR_NAME), &results_dir,
+                                      &file_filter, &no_exif_config,
+                                      &exif_config, &reorganize_config, show_progress).unwrap();
+
+        let expected_dest_file_path = results_dir.join("2020/Октябрь/2020-10-10__11-09-47__IMG_20201010_120947.jpg");
+
+        assert_planned(&report, &expected_dest_file_path);
+
+        fs::remove_dir_all(&test_dir).unwrap();
+    }
+
+    #[test]
+    fn raw_file_should_get_date_of_jpeg_with_same_name() {
+        let test_dir = get_test_dir("raw_file_should_get_date_of_jpeg_with_same_name");
+        let src_dir = test_dir.join("src");
+        let results_dir = test_dir.join("results");
+
+        fs::create_dir_all(&src_dir).unwrap();
+        fs::copy(format!("{}/20201010/IMG_20201010_120947.jpg", SOURCE_DIR_NAME),
+                 src_dir.join("IMG_0001.jpg")).unwrap();
+        fs::write(src_dir.join("IMG_0001.CR2"), b"II*\0raw data").unwrap();
+
+        let no_exif_config = get_no_exif_config(false);
+
+        let mut reorganize_config = get_reorganize_config();
+        reorganize_config.dry_run = true;
+
+        let file_filter = get_file_filter();
+
+        let report = reorganize_files(&src_dir, &results_dir,
+                                      &file_filter, &no_exif_config,
+                                      &get_exif_config(), &reorganize_config, show_progress).unwrap();
+
+        assert_eq!(report.planned_files.len(), 1);
+
+        reorganize_config.group_raw = true;
+
+        let report = reorganize_files(&src_dir, &results_dir,
+                                      &file_filter, &no_exif_config,
+                                      &get_exif_config(), &reorganize_config, show_progress).unwrap();
+
+        let expected_dest_file_path = results_dir.join("2020/Октябрь/2020-10-10__12-09-47__IMG_0001.CR2");
+
+        assert_eq!(report.planned_files.len(), 2);
+        assert_planned(&report, &expected_dest_file_path);
+
+        fs::remove_dir_all(&test_dir).unwrap();
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn symlinked_jpeg_should_not_be_raw_companion_without_follow_symlinks() {
+        let test_dir = get_test_dir("symlinked_jpeg_should_not_be_raw_companion_without_follow_symlinks");
+        let src_dir = test_dir.join("src");
+        let results_dir = test_dir.join("results");
+
+        fs::create_dir_all(&src_dir).unwrap();
+        std::os::unix::fs::symlink(
+            fs::canonicalize(format!("{}/20201010/IMG_20201010_120947.jpg", SOURCE_DIR_NAME)).unwrap(),
+            src_dir.join("IMG_0001.jpg")
+        ).unwrap();
+        fs::write(src_dir.join("IMG_0001.CR2"), b"II*\0raw data").unwrap();
+
+        let no_exif_config = get_no_exif_config(false);
+
+        let mut reorganize_config = get_reorganize_config();
+        reorganize_config.dry_run = true;
+        reorganize_config.group_raw = true;
+
+        let mut file_filter = get_file_filter();
+
+        let report = reorganize_files(&src_dir, &results_dir,
+                                      &file_filter, &no_exif_config,
+                                      &get_exif_config(), &reorganize_config, show_progress).unwrap();
+
+        assert!(report.planned_files.is_empty());
+
+        file_filter.follow_symlinks = true;
+
+        let report = reorganize_files(&src_dir, &results_dir,
+                                      &file_filter, &no_exif_config,
+                                      &get_exif_config(), &reorganize_config, show_progress).unwrap();
+
+        assert_eq!(report.planned_files.len(), 2);
+
+        fs::remove_dir_all(&test_dir).unwrap();
+    }
+
+    #[test]
+    fn excluded_jpeg_should_not_be_raw_companion() {
+        let test_dir = get_test_dir("excluded_jpeg_should_not_be_raw_companion");
+        let src_dir = test_dir.join("src");
+        let results_dir = test_dir.join("results");
+
+        fs::create_dir_all(src_dir.join("photos")).unwrap();
+
+        for file_name in ["IMG_0001", "photos/IMG_0002"] {
+            fs::copy(format!("{}/20201010/IMG_20201010_120947.jpg", SOURCE_DIR_NAME),
+                     src_dir.join(format!("{}.jpg", file_name))).unwrap();
+            fs::write(src_dir.join(format!("{}.CR2", file_name)), b"II*\0raw data").unwrap();
+        }
+
+        fs::write(src_dir.join("photos/.imgtagignore"), "IMG_0002.jpg\n").unwrap();
+
+        let no_exif_config = get_no_exif_config(false);
+
+        let mut reorganize_config = get_reorganize_config();
+        reorganize_config.dry_run = true;
+        reorganize_config.group_raw = true;
+
+        let mut file_filter = get_file_filter();
+        file_filter.exclude_rules = parse_exclude_patterns(&vec![String::from("/IMG_0001.jpg")]).unwrap();
+
+        let report = reorganize_files(&src_dir, &results_dir,
+                                      &file_filter, &no_exif_config,
+                                      &get_exif_config(), &reorganize_config, show_progress).unwrap();
+
+        assert!(report.planned_files.is_empty());
+        assert_eq!(report.excluded_paths, 2);
+
+        fs::remove_dir_all(&test_dir).unwrap();
+    }
+
+    #[test]
+    fn misnamed_file_should_be_accepted_by_content() {
+        let test_dir = get_test_dir("misnamed_file_should_be_accepted_by_content");
+        let src_dir = test_dir.join("src");
+        let results_dir = test_dir.join("results");
+
+        fs::create_dir_all(&src_dir).unwrap();
+        fs::copy(format!("{}/20201010/IMG_20201010_120947.jpg", SOURCE_DIR_NAME),
+                 src_dir.join("photo.dat")).unwrap();
+        fs::write(src_dir.join("notes.dat"), b"not an image at all").unwrap();
+
+        let no_exif_config = get_no_exif_config(false);
+
+        let mut reorganize_config = get_reorganize_config();
+        reorganize_config.dry_run = true;
+
+        let mut file_filter = get_file_filter();
+
+        let report = reorganize_files(&src_dir, &results_dir,
+                                      &file_filter, &no_exif_config,
+                                      &get_exif_config(), &reorganize_config, show_progress).unwrap();
+
+        assert_eq!(report.files_total, 0);
+
+        file_filter.sniff_content = true;
+
+        let report = reorganize_files(&src_dir, &results_dir,
+                                      &file_filter, &no_exif_config,
+                                      &get_exif_config(), &reorganize_config, show_progress).unwrap();
+
+        assert_eq!(report.files_total, 1);
+        assert_planned(&report, &results_dir.join("2020/Октябрь/2020-10-10__12-09-47__photo.dat"));
+
+        fs::remove_dir_all(&test_dir).unwrap();
+    }
+
+    #[test]
+    fn excluded_paths_should_be_skipped_and_counted() {
+        let test_dir = get_test_dir("excluded_paths_should_be_skipped_and_counted");
+        let src_dir = test_dir.join("src");
+        let results_dir = test_dir.join("results");
+
+        let sample_file_path = format!("{}/20201010/IMG_20201010_120947.jpg", SOURCE_DIR_NAME);
+
+        for dir_name in ["photos", "photos/.thumbnails", "photos/@eaDir", "photos/cache"] {
+            fs::create_dir_all(src_dir.join(dir_name)).unwrap();
+            fs::copy(&sample_file_path, src_dir.join(dir_name).join("IMG_0001.jpg")).unwrap();
+        }
+
+        fs::write(src_dir.join("photos/.imgtagignore"), "cache/\n").unwrap();
+
+        let no_exif_config = get_no_exif_config(false);
+
+        let mut reorganize_config = get_reorganize_config();
+        reorganize_config.dry_run = true;
+
+        let mut file_filter = get_file_filter();
+        file_filter.exclude_rules = parse_exclude_patterns(
+            &vec![String::from(".thumbnails"), String::from("re:@eaDir$")]
+        ).unwrap();
+
+        let report = reorganize_files(&src_dir, &results_dir,
+                                      &file_filter, &no_exif_config,
+                                      &get_exif_config(), &reorganize_config, show_progress).unwrap();
+
+        assert_eq!(report.files_total, 1);
+        assert_eq!(report.planned_files[0].src_file_path,
+                   src_dir.join("photos/IMG_0001.jpg"));
+        assert_eq!(report.excluded_paths, 3);
+
+        fs::remove_dir_all(&test_dir).unwrap();
+    }
+
+    #[test]
+    fn collisions_should_be_resolved_in_order_with_workers() {
+        let test_dir = get_test_dir("collisions_should_be_resolved_in_order_with_workers");
+        let src_dir = test_dir.join("src");
+        let results_dir = test_dir.join("results");
+
+        let sample_file_path = format!("{}/20201010/IMG_20201010_120947.jpg", SOURCE_DIR_NAME);
+        let sample_contents = fs::read(&sample_file_path).unwrap();
+
+        fs::create_dir_all(&src_dir).unwrap();
+
+        // same date and name, different content
+        for index in 0..8 {
+            let dir_path = src_dir.join(index.to_string());
+            fs::create_dir_all(&dir_path).unwrap();
+
+            let mut contents = sample_contents.clone();
+            contents.push(index as u8);
+            fs::write(dir_path.join("IMG_20201010_120947.jpg"), contents).unwrap();
+        }
+
+        let no_exif_config = get_no_exif_config(false);
+
+        let mut reorganize_config = get_reorganize_config();
+        reorganize_config.jobs = 4;
+
+        let report = reorganize_files(&src_dir, &results_dir,
+                                      &get_file_filter(), &no_exif_config,
+                                      &get_exif_config(), &reorganize_config, show_progress).unwrap();
+
+        assert_eq!(report.files_total, 8);
+        assert_eq!(report.collisions.len(), 7);
+
+        for (index, collision) in report.collisions.iter().enumerate() {
+            match &collision.decision {
+                CollisionDecision::Renamed(new_file_path) => {
+                    assert!(new_file_path.to_string_lossy().ends_with(&format!("_{}.jpg", index + 1)));
+                    assert_eq!(fs::read(new_file_path).unwrap(),
+                               fs::read(&collision.src_file_path).unwrap());
+                }
+                _ => panic!("file should be renamed")
+            }
+        }
+
+        // second run finds all files in place
+        let report = reorganize_files(&src_dir, &results_dir,
+                                      &get_file_filter(), &no_exif_config,
+                                      &get_exif_config(), &reorganize_config, show_progress).unwrap();
+
+        assert!(report.collisions.iter().all(|collision|
+            match collision.decision {
+                CollisionDecision::SkippedIdentical => true,
+                _ => false
+            }
+        ));
+
+        fs::remove_dir_all(&test_dir).unwrap();
+    }
+
+    #[test]
+    fn only_one_copy_should_be_placed_with_dedupe() {
+        let test_dir = get_test_dir("only_one_copy_should_be_placed_with_dedupe");
+        let src_dir = test_dir.join("src");
+        let results_dir = test_dir.join("results");
+
+        let sample_file_path = format!("{}/20201010/IMG_20201010_120947.jpg", SOURCE_DIR_NAME);
+        let sample_contents = fs::read(&sample_file_path).unwrap();
+
+        // same content under different names in backup folders
+        for (dir_name, file_name) in [("a", "IMG_0001.jpg"), ("b", "IMG_0002.jpg"),
+                                      ("c", "IMG_0003.jpg")] {
+            fs::create_dir_all(src_dir.join(dir_name)).unwrap();
+            fs::write(src_dir.join(dir_name).join(file_name),
+                      &sample_contents).unwrap();
+        }
+
+        let mut other_contents = sample_contents.clone();
+        other_contents.push(0);
+        fs::write(src_dir.join("c/IMG_0004.jpg"), other_contents).unwrap();
+
+        let no_exif_config = get_no_exif_config(false);
+
+        let mut reorganize_config = get_reorganize_config();
+        reorganize_config.dedupe = true;
+        reorganize_config.jobs = 2;
+
+        let report = reorganize_files(&src_dir, &results_dir,
+                                      &get_file_filter(), &no_exif_config,
+                                      &get_exif_config(), &reorganize_config, show_progress).unwrap();
+
+        assert_eq!(report.files_total, 4);
+        assert_eq!(report.duplicates.len(), 2);
+
+        for duplicate in report.duplicates.iter() {
+            assert_ne!(duplicate.src_file_path, duplicate.original_file_path);
+            assert_eq!(fs::read(&duplicate.original_file_path).unwrap(), sample_contents);
+        }
+
+        let placed_files = fs::read_dir(results_dir.join("2020/Октябрь")).unwrap().count();
+        assert_eq!(placed_files, 2);
+
+        fs::remove_dir_all(&test_dir).unwrap();
+    }
+
+    #[test]
+    fn files_without_date_should_be_copied_to_unknown_dir() {
+        let test_dir = get_test_dir("files_without_date_should_be_copied_to_unknown_dir");
+        let src_dir = test_dir.join("src");
+        let results_dir = test_dir.join("results");
+        let unknown_dir = test_dir.join("unknown");
+
+        fs::create_dir_all(src_dir.join("trip")).unwrap();
+        fs::copy(format!("{}/20201010/IMG_20201010_120947.jpg", SOURCE_DIR_NAME),
+                 src_dir.join("trip/IMG_0001.jpg")).unwrap();
+        fs::write(src_dir.join("trip/IMG_0002.jpg"), "not a jpeg").unwrap();
+
+        let no_exif_config = get_no_exif_config(false);
+
+        let mut reorganize_config = get_reorganize_config();
+        reorganize_config.placement_mode = PlacementMode::Move;
+
+        let report = reorganize_files(&src_dir, &results_dir,
+                                      &get_file_filter(), &no_exif_config,
+                                      &get_exif_config(), &reorganize_config, show_progress).unwrap();
+
+        assert_eq!(report.placed_files, 1);
+        assert_eq!(report.get_skipped_files(), 1);
+        assert_eq!(report.get_unplaced_files(), 1);
+        assert_eq!(report.undated_files[0].src_file_path,
+                   src_dir.join("trip/IMG_0002.jpg"));
+
+        reorganize_config.unknown_dir = Some(unknown_dir.clone());
+
+        let report = reorganize_files(&src_dir, &results_dir,
+                                      &get_file_filter(), &no_exif_config,
+                                      &get_exif_config(), &reorganize_config, show_progress).unwrap();
+
+        let unknown_file_path = unknown_dir.join("trip/IMG_0002.jpg");
+
+        assert_eq!(report.placed_files, 1);
+        assert_eq!(report.get_unplaced_files(), 0);
+        assert_eq!(report.undated_files[0].unknown_file_path.as_ref(), Some(&unknown_file_path));
+
+        // files without date are copied even in move mode
+        assert!(unknown_file_path.exists());
+        assert!(src_dir.join("trip/IMG_0002.jpg").exists());
+
+        fs::remove_dir_all(&test_dir).unwrap();
+    }
+
+    #[test]
+    fn modification_time_should_be_used_as_fallback_with_suffix() {
+        let test_dir = get_test_dir("modification_time_should_be_used_as_fallback_with_suffix");
+        let src_dir = test_dir.join("src");
+        let results_dir = test_dir.join("results");
+
+        fs::create_dir_all(&src_dir).unwrap();
+
+        let file_path = src_dir.join("IMG_0001.jpg");
+        fs::write(&file_path, "not a jpeg").unwrap();
+
+        // 2015-03-04 12:30:00 UTC
+        let modified = UNIX_EPOCH + Duration::from_secs(1425472200);
+        File::options().write(true).open(&file_path).unwrap().set_modified(modified).unwrap();
+
+        let mut no_exif_config = get_no_exif_config(true);
+        no_exif_config.fallback = Some(FileTimestamp::Modified);
+
+        let mut exif_config = get_exif_config();
+        exif_config.timezone = Some(TargetTimeZone::Utc);
+
+        let mut reorganize_config = get_reorganize_config();
+        reorganize_config.dry_run = true;
+
+        let report = reorganize_files(&src_dir, &results_dir,
+                                      &get_file_filter(), &no_exif_config,
+                                      &exif_config, &reorganize_config, show_progress).unwrap();
+
+        let planned_file = assert_planned(
+            &report, &results_dir.join("2015/Март/2015-03-04__12-30-00__IMG_0001__mtime.jpg")
+        );
+        assert_eq!(planned_file.date_source, DateSource::FileTime(FileTimestamp::Modified));
+        assert_eq!(planned_file.date_source.to_string(), "fs:mtime");
+
+        // fallback is checked before forced year
+        no_exif_config.force_year = true;
+        no_exif_config.year = 2010;
+
+        let report = reorganize_files(&src_dir, &results_dir,
+                                      &get_file_filter(), &no_exif_config,
+                                      &exif_config, &reorganize_config, show_progress).unwrap();
+
+        assert_eq!(report.planned_files[0].date_source, DateSource::FileTime(FileTimestamp::Modified));
+
+        fs::remove_dir_all(&test_dir).unwrap();
+    }
+
+    #[test]
+    fn status_change_time_should_be_used_as_fallback() {
+        let test_dir = get_test_dir("status_change_time_should_be_used_as_fallback");
+        let src_dir = test_dir.join("src");
+        let results_dir = test_dir.join("results");
+
+        fs::create_dir_all(&src_dir).unwrap();
+        fs::write(src_dir.join("IMG_0001.jpg"), "not a jpeg").unwrap();
+
+        let mut no_exif_config = get_no_exif_config(true);
+        no_exif_config.fallback = Some(FileTimestamp::Changed);
+
+        let mut reorganize_config = get_reorganize_config();
+        reorganize_config.dry_run = true;
+
+        let report = reorganize_files(&src_dir, &results_dir,
+                                      &get_file_filter(), &no_exif_config,
+                                      &get_exif_config(), &reorganize_config, show_progress).unwrap();
+
+        let planned_file = &report.planned_files[0];
+        assert_eq!(planned_file.date_source, DateSource::FileTime(FileTimestamp::Changed));
+        assert!(planned_file.dest_file_path.to_string_lossy().ends_with("__IMG_0001__ctime.jpg"));
+
+        fs::remove_dir_all(&test_dir).unwrap();
+    }
+
+    #[test]
+    fn skipped_file_should_not_be_original_with_dedupe() {
+        let test_dir = get_test_dir("skipped_file_should_not_be_original_with_dedupe");
+        let src_dir = test_dir.join("src");
+        let results_dir = test_dir.join("results");
+
+        let sample_contents = fs::read(
+            format!("{}/20201010/IMG_20201010_120947.jpg", SOURCE_DIR_NAME)
+        ).unwrap();
+
+        for dir_name in ["a", "b"] {
+            fs::create_dir_all(src_dir.join(dir_name)).unwrap();
+        }
+
+        fs::write(src_dir.join("a/IMG_0001.jpg"), &sample_contents).unwrap();
+        fs::write(src_dir.join("b/IMG_0001.jpg"), &sample_contents).unwrap();
+
+        // destination of both copies is occupied by other file
+        fs::create_dir_all(results_dir.join("2020/Октябрь")).unwrap();
+        fs::write(results_dir.join("2020/Октябрь/2020-10-10__12-09-47__IMG_0001.jpg"),
+                  "other file").unwrap();
+
+        let no_exif_config = get_no_exif_config(false);
+
+        let mut reorganize_config = get_reorganize_config();
+        reorganize_config.dedupe = true;
+        reorganize_config.on_conflict = ConflictPolicy::Skip;
+
+        let report = reorganize_files(&src_dir, &results_dir,
+                                      &get_file_filter(), &no_exif_config,
+                                      &get_exif_config(), &reorganize_config, show_progress).unwrap();
+
+        assert_eq!(report.collisions.len(), 2);
+        assert!(report.duplicates.is_empty());
+
+        fs::remove_dir_all(&test_dir).unwrap();
+    }
+
+    #[test]
+    fn files_without_date_should_be_deduplicated_in_unknown_dir() {
+        let test_dir = get_test_dir("files_without_date_should_be_deduplicated_in_unknown_dir");
+        let src_dir = test_dir.join("src");
+        let results_dir = test_dir.join("results");
+        let unknown_dir = test_dir.join("unknown");
+
+        fs::create_dir_all(src_dir.join("a")).unwrap();
+        fs::create_dir_all(src_dir.join("b")).unwrap();
+        fs::write(src_dir.join("a/IMG_0001.jpg"), "not a jpeg").unwrap();
+        fs::write(src_dir.join("b/IMG_0001.jpg"), "not a jpeg").unwrap();
+
+        let no_exif_config = get_no_exif_config(false);
+
+        let mut reorganize_config = get_reorganize_config();
+        reorganize_config.dedupe = true;
+        reorganize_config.unknown_dir = Some(unknown_dir.clone());
+
+        let report = reorganize_files(&src_dir, &results_dir,
+                                      &get_file_filter(), &no_exif_config,
+                                      &get_exif_config(), &reorganize_config, show_progress).unwrap();
+
+        assert_eq!(report.duplicates.len(), 1);
+        assert_eq!(report.undated_files.len(), 1);
+        assert_eq!(report.get_unplaced_files(), 0);
+
+        fs::remove_dir_all(&test_dir).unwrap();
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn symlinks_should_be_followed_once_with_option() {
+        use std::os::unix::fs::symlink;
+
+        let test_dir = get_test_dir("symlinks_should_be_followed_once_with_option");
+        let src_dir = test_dir.join("src");
+        let results_dir = test_dir.join("results");
+
+        fs::create_dir_all(src_dir.join("album")).unwrap();
+        fs::create_dir_all(src_dir.join("loop")).unwrap();
+        fs::copy(format!("{}/20201010/IMG_20201010_120947.jpg", SOURCE_DIR_NAME),
+                 src_dir.join("album/IMG_0001.jpg")).unwrap();
+
+        symlink("album", src_dir.join("shared")).unwrap();
+        symlink("..", src_dir.join("loop/parent")).unwrap();
+        symlink("album/IMG_0001.jpg", src_dir.join("IMG_0001.txt")).unwrap();
+        symlink("missing.jpg", src_dir.join("broken.jpg")).unwrap();
+
+        let no_exif_config = get_no_exif_config(false);
+
+        let mut reorganize_config = get_reorganize_config();
+        reorganize_config.dry_run = true;
+
+        let report = reorganize_files(&src_dir, &results_dir,
+                                      &get_file_filter(), &no_exif_config,
+                                      &get_exif_config(), &reorganize_config, show_progress).unwrap();
+
+        assert_eq!(report.files_total, 1);
+        assert!(report.broken_links.is_empty());
+
+        let mut file_filter = get_file_filter();
+        file_filter.follow_symlinks = true;
+
+        let report = reorganize_files(&src_dir, &results_dir,
+                                      &file_filter, &no_exif_config,
+                                      &get_exif_config(), &reorganize_config, show_progress).unwrap();
+
+        assert_eq!(report.files_total, 1);
+        assert_eq!(report.broken_links, vec![src_dir.join("broken.jpg")]);
+
+        fs::remove_dir_all(&test_dir).unwrap();
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn non_utf8_file_name_should_be_kept_or_decoded() {
+        use std::ffi::OsStr;
+        use std::os::unix::ffi::OsStrExt;
+        use crate::domain::domain::NameEncoding;
+
+        let test_dir = get_test_dir("non_utf8_file_name_should_be_kept_or_decoded");
+        let src_dir = test_dir.join("src");
+        let results_dir = test_dir.join("results");
+
+        fs::create_dir_all(&src_dir).unwrap();
+
+        // 'Привет.jpg' in cp1251
+        let file_name = OsStr::from_bytes(b"\xcf\xf0\xe8\xe2\xe5\xf2.jpg");
+
+        fs::copy(format!("{}/20201010/IMG_20201010_120947.jpg", SOURCE_DIR_NAME),
+                 src_dir.join(file_name)).unwrap();
+
+        let no_exif_config = get_no_exif_config(false);
+
+        let mut reorganize_config = get_reorganize_config();
+        reorganize_config.dry_run = true;
+
+        let report = reorganize_files(&src_dir, &results_dir,
+                                      &get_file_filter(), &no_exif_config,
+                                      &get_exif_config(), &reorganize_config, show_progress).unwrap();
+
+        let mut expected_file_name = OsStr::new("2020-10-10__12-09-47__").to_os_string();
+        expected_file_name.push(file_name);
+
+        assert_eq!(report.planned_files[0].dest_file_path.file_name().unwrap(), expected_file_name);
+
+        reorganize_config.name_encoding = Some(NameEncoding::Cp1251);
+
+        let report = reorganize_files(&src_dir, &results_dir,
+                                      &get_file_filter(), &no_exif_config,
+                                      &get_exif_config(), &reorganize_config, show_progress).unwrap();
+
+        assert_planned(&report, &results_dir.join("2020/Октябрь/2020-10-10__12-09-47__Привет.jpg"));
+
+        fs::remove_dir_all(&test_dir).unwrap();
+    }
+
+    #[test]
+    fn traversal_error_should_be_reported_and_other_files_processed() {
+        let test_dir = get_test_dir("traversal_error_should_be_reported_and_other_files_processed");
+        let src_dir = test_dir.join("src");
+        let results_dir = test_dir.join("results");
+
+        let sample_file_path = format!("{}/20201010/IMG_20201010_120947.jpg", SOURCE_DIR_NAME);
+
+        for dir_name in ["broken", "photos"] {
+            fs::create_dir_all(src_dir.join(dir_name)).unwrap();
+            fs::copy(&sample_file_path, src_dir.join(dir_name).join("IMG_0001.jpg")).unwrap();
+        }
+
+        fs::write(src_dir.join("broken/.imgtagignore"), "!\n").unwrap();
+
+        let no_exif_config = get_no_exif_config(false);
+
+        let mut reorganize_config = get_reorganize_config();
+        reorganize_config.dry_run = true;
+
+        let report = reorganize_files(&src_dir, &results_dir,
+                                      &get_file_filter(), &no_exif_config,
+                                      &get_exif_config(), &reorganize_config, show_progress).unwrap();
+
+        assert_eq!(report.files_total, 1);
+        assert_eq!(report.planned_files[0].src_file_path,
+                   src_dir.join("photos/IMG_0001.jpg"));
+        assert_eq!(report.traversal_errors.len(), 1);
+        assert!(report.traversal_errors[0].contains("broken"));
+
+        fs::remove_dir_all(&test_dir).unwrap();
+    }
+
+    fn get_no_exif_config(extract_dates_from_path: bool) -> NoExifConfig {
+        NoExifConfig {
+            extract_dates_from_path,
+            skip_dir_names_for_date_extract: Vec::new(),
+            force_year: false,
+            year: 0,
+            fallback: None
+        }
+    }
+
+    fn get_file_filter() -> FileFilter {
+        FileFilter {
+            extensions: get_extension_filters(),
+            sniff_content: false,
+            exclude_rules: ExcludeRules::new(),
+            follow_symlinks: false
+        }
+    }
+
+    fn get_exif_config() -> ExifConfig {
+        ExifConfig {
+            date_tags: DEFAULT_DATE_TAGS.to_vec(),
+            timezone: None,
+            time_shift: TimeShiftConfig { rules: Vec::new(), default_shift: None }
+        }
+    }
+
+    fn get_reorganize_config() -> ReorganizeConfig {
+        ReorganizeConfig {
+            dry_run: false,
+            placement_mode: PlacementMode::Copy,
+            on_conflict: ConflictPolicy::Rename,
+            template: parse_template(DEFAULT_TEMPLATE).unwrap(),
+            month_names: get_month_names(DEFAULT_LOCALE).unwrap(),
+            with_milliseconds: false,
+            group_raw: false,
+            jobs: 1,
+            name_encoding: None,
+            dedupe: false,
+            unknown_dir: None
+        }
+    }
+
+    /// Planned file with given destination, fails test if there is no such file.
+    fn assert_planned<'a>(report: &'a ReorganizeReport, dest_file_path: &Path) -> &'a PlannedFile {
+        match report.planned_files.iter().find(|planned_file| planned_file.dest_file_path == dest_file_path) {
+            Some(planned_file) => planned_file,
+            None => panic!("file '{}' hasn't been planned", dest_file_path.display())
+        }
+    }
+
+    /// Empty directory for test files, should be removed at the end of test.
+    fn get_test_dir(test_name: &str) -> PathBuf {
+        let dir_path = env::temp_dir().join("imgtag-tests").join(test_name);
+
+        if dir_path.exists() {
+            fs::remove_dir_all(&dir_path).unwrap();
+        }
+
+        fs::create_dir_all(&dir_path).unwrap();
+        dir_path
+    }
+
     fn remove_results_dir() {
         let results_path = Path::new(RESULTS_DIR_NAME);
 
@@ -101,9 +1078,9 @@ pub mod commands_tests {
         }
     }
 
-    fn show_progress(total_elements: usize, current_element_index: usize) {
+    fn show_progress(discovered_files: usize, processed_files: usize) {
         print!("\r");
-        print!("progress: {}/{}", current_element_index, total_elements);
+        print!("progress: {} (discovered: {})", processed_files, discovered_files);
     }
 
 }
@@ -0,0 +1,442 @@
+pub mod containers {
+    use std::fmt;
+    use std::fmt::{Display, Formatter};
+    use std::path::Path;
+
+    /// Image formats with EXIF support
+    pub const IMAGE_EXTENSIONS: [&str; 7] = ["jpg", "jpeg", "tiff", "heic", "heif", "png", "webp"];
+
+    /// Camera RAW formats. CR2, NEF, ARW and DNG are TIFF files,
+    /// ORF is TIFF with custom magic number, RAF contains JPEG preview with EXIF.
+    pub const RAW_EXTENSIONS: [&str; 6] = ["cr2", "nef", "arw", "dng", "orf", "raf"];
+
+    const RAF_SIGNATURE: &[u8] = b"FUJIFILMCCD-RAW";
+    const RAF_JPEG_OFFSET_POSITION: usize = 84;
+
+    const PNG_SIGNATURE: [u8; 8] = [0x89, b'P', b'N', b'G', 0x0D, 0x0A, 0x1A, 0x0A];
+    const PNG_EXIF_CHUNK: &[u8] = b"eXIf";
+    const PNG_END_CHUNK: &[u8] = b"IEND";
+
+    const RIFF_SIGNATURE: &[u8] = b"RIFF";
+    const WEBP_SIGNATURE: &[u8] = b"WEBP";
+    const WEBP_EXIF_CHUNK: &[u8] = b"EXIF";
+
+    const HEIF_BRANDS: [&[u8]; 9] = [
+        b"heic", b"heix", b"hevc", b"hevx", b"heim", b"heis", b"mif1", b"msf1", b"avif"
+    ];
+
+    /// Prefix of EXIF data in JPEG APP1 segment, some writers keep it in other containers too
+    const EXIF_HEADER: &[u8] = b"Exif\0\0";
+
+    /// Top-level boxes of QuickTime files without `ftyp`
+    const QUICKTIME_BOXES: [&[u8]; 3] = [b"moov", b"mdat", b"wide"];
+
+    /// Kind of supported file, detected by content.
+    #[derive(Clone, Copy, PartialEq, Debug)]
+    pub enum ContentType {
+        Image,
+        Video
+    }
+
+    /// Image containers which keep EXIF outside of JPEG/TIFF structure.
+    #[derive(Clone, Copy, PartialEq, Debug)]
+    pub enum ImageContainer {
+        Heif,
+        Png,
+        WebP,
+
+        /// Fujifilm RAW
+        Raf
+    }
+
+    impl Display for ImageContainer {
+        fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+            let name = match self {
+                ImageContainer::Heif => "HEIF",
+                ImageContainer::Png => "PNG",
+                ImageContainer::WebP => "WebP",
+                ImageContainer::Raf => "RAF"
+            };
+
+            write!(f, "{}", name)
+        }
+    }
+
+    /// Detect container by signature. JPEG and TIFF are handled by EXIF parser itself.
+    pub fn detect_container(contents: &[u8]) -> Option<ImageContainer> {
+        if contents.starts_with(&PNG_SIGNATURE) {
+            Some(ImageContainer::Png)
+
+        } else if contents.len() >= 12 && &contents[0..4] == RIFF_SIGNATURE &&
+                  &contents[8..12] == WEBP_SIGNATURE {
+            Some(ImageContainer::WebP)
+
+        } else if is_heif(contents) {
+            Some(ImageContainer::Heif)
+
+        } else if contents.starts_with(RAF_SIGNATURE) {
+            Some(ImageContainer::Raf)
+
+        } else {
+            None
+        }
+    }
+
+    /// Find EXIF data (TIFF structure or JPEG with EXIF) inside container.
+    /// Returns `None` if container doesn't contain EXIF.
+    pub fn find_exif_data(contents: &[u8], container: ImageContainer) -> Option<&[u8]> {
+        let exif_data = match container {
+            ImageContainer::Png => find_png_exif(contents),
+            ImageContainer::WebP => find_webp_exif(contents),
+            ImageContainer::Heif => find_heif_exif(contents),
+            ImageContainer::Raf => find_raf_jpeg(contents)
+        }?;
+
+        if exif_data.starts_with(EXIF_HEADER) {
+            Some(&exif_data[EXIF_HEADER.len()..])
+
+        } else {
+            Some(exif_data)
+        }
+    }
+
+    /// Detect supported file by magic bytes from file header.
+    pub fn sniff_content_type(header: &[u8]) -> Option<ContentType> {
+        if header.len() < 12 {
+            return None
+        }
+
+        let is_jpeg = header.starts_with(&[0xFF, 0xD8, 0xFF]);
+
+        let is_tiff = header.starts_with(b"II*\0") || header.starts_with(b"MM\0*") ||
+            header.starts_with(b"IIRO") || header.starts_with(b"IIRS") ||
+            header.starts_with(b"MMOR");
+
+        if is_jpeg || is_tiff || detect_container(header).is_some() {
+            Some(ContentType::Image)
+
+        } else if &header[4..8] == b"ftyp" || QUICKTIME_BOXES.contains(&&header[4..8]) {
+            Some(ContentType::Video)
+
+        } else {
+            None
+        }
+    }
+
+    pub fn is_raw_file(file_path: &Path) -> bool {
+        match file_path.extension().and_then(|ext| ext.to_str()) {
+            Some(ext) => RAW_EXTENSIONS.contains(&ext.to_lowercase().as_str()),
+            None => false
+        }
+    }
+
+    /// Olympus ORF uses `IIRO`, `IIRS` or `MMOR` instead of TIFF magic number.
+    /// Replace it with TIFF one, so file can be parsed as TIFF.
+    pub fn fix_raw_tiff_header(contents: &mut [u8]) {
+        if contents.len() < 4 {
+            return
+        }
+
+        match &contents[0..4] {
+            b"IIRO" | b"IIRS" => contents[2..4].copy_from_slice(&[42, 0]),
+            b"MMOR" => contents[2..4].copy_from_slice(&[0, 42]),
+            _ => {}
+        }
+    }
+
+    /// RAF header contains offset and length of embedded JPEG.
+    fn find_raf_jpeg(contents: &[u8]) -> Option<&[u8]> {
+        let jpeg_offset = read_u32_be(contents, RAF_JPEG_OFFSET_POSITION)? as usize;
+        let jpeg_length = read_u32_be(contents, RAF_JPEG_OFFSET_POSITION + 4)? as usize;
+
+        contents.get(jpeg_offset..jpeg_offset.checked_add(jpeg_length)?)
+    }
+
+    fn find_png_exif(contents: &[u8]) -> Option<&[u8]> {
+        let mut offset = PNG_SIGNATURE.len();
+
+        while offset + 8 <= contents.len() {
+            let length = read_u32_be(contents, offset)? as usize;
+            let chunk_type = &contents[offset + 4..offset + 8];
+            let data_start = offset + 8;
+            let data_end = data_start.checked_add(length)?;
+
+            if data_end > contents.len() {
+                return None
+            }
+
+            if chunk_type == PNG_EXIF_CHUNK {
+                return Some(&contents[data_start..data_end])
+            }
+
+            if chunk_type == PNG_END_CHUNK {
+                return None
+            }
+
+            // data + crc
+            offset = data_end + 4;
+        }
+
+        None
+    }
+
+    fn find_webp_exif(contents: &[u8]) -> Option<&[u8]> {
+        let mut offset = 12;
+
+        while offset + 8 <= contents.len() {
+            let chunk_type = &contents[offset..offset + 4];
+            let size = read_u32_le(contents, offset + 4)? as usize;
+            let data_start = offset + 8;
+            let data_end = data_start.checked_add(size)?;
+
+            if data_end > contents.len() {
+                return None
+            }
+
+            if chunk_type == WEBP_EXIF_CHUNK {
+                return Some(&contents[data_start..data_end])
+            }
+
+            // chunks are padded to even size
+            offset = data_end + (size % 2);
+        }
+
+        None
+    }
+
+    fn is_heif(contents: &[u8]) -> bool {
+        match find_box(contents, 0, contents.len(), b"ftyp") {
+            Some((start, end)) if start == 8 => {
+                // major brand, minor version, compatible brands
+                let major_brand = &contents[start..(start + 4).min(end)];
+
+                HEIF_BRANDS.contains(&major_brand) ||
+                    contents[(start + 8).min(end)..end].chunks(4)
+                        .any(|brand| HEIF_BRANDS.contains(&brand))
+            }
+            _ => false
+        }
+    }
+
+    /// EXIF is stored as item with type `Exif`, item location is described in `iloc` box.
+    fn find_heif_exif(contents: &[u8]) -> Option<&[u8]> {
+        let (meta_start, meta_end) = find_box(contents, 0, contents.len(), b"meta")?;
+
+        // full box: version and flags
+        let meta_start = meta_start + 4;
+
+        let (iinf_start, iinf_end) = find_box(contents, meta_start, meta_end, b"iinf")?;
+        let item_id = find_exif_item_id(contents, iinf_start, iinf_end)?;
+
+        let (iloc_start, iloc_end) = find_box(contents, meta_start, meta_end, b"iloc")?;
+        let location = find_item_location(contents, iloc_start, iloc_end, item_id)?;
+
+        let data_start = match location.construction_method {
+            0 => location.offset,
+            1 => {
+                let (idat_start, _) = find_box(contents, meta_start, meta_end, b"idat")?;
+                idat_start.checked_add(location.offset)?
+            }
+            _ => return None
+        };
+
+        let data_end = data_start.checked_add(location.length)?;
+
+        if data_end > contents.len() || location.length < 4 {
+            return None
+        }
+
+        // Item data starts with offset to TIFF header
+        let tiff_header_offset = read_u32_be(contents, data_start)? as usize;
+        let tiff_start = data_start.checked_add(4)?.checked_add(tiff_header_offset)?;
+
+        if tiff_start >= data_end {
+            return None
+        }
+
+        Some(&contents[tiff_start..data_end])
+    }
+
+    fn find_exif_item_id(contents: &[u8], start: usize, end: usize) -> Option<u32> {
+        let version = *contents.get(start)?;
+
+        let entries_start = if version == 0 { start + 6 } else { start + 8 };
+
+        let mut offset = entries_start;
+
+        while let Some((infe_start, infe_end)) = find_box(contents, offset, end, b"infe") {
+            let infe_version = *contents.get(infe_start)?;
+
+            // item type is available since version 2
+            if infe_version >= 2 {
+                let (item_id, item_type_offset) = if infe_version == 2 {
+                    (read_u16_be(contents, infe_start + 4)? as u32, infe_start + 8)
+                } else {
+                    (read_u32_be(contents, infe_start + 4)?, infe_start + 10)
+                };
+
+                if contents.get(item_type_offset..item_type_offset + 4)? == b"Exif" {
+                    return Some(item_id)
+                }
+            }
+
+            offset = infe_end;
+        }
+
+        None
+    }
+
+    struct ItemLocation {
+        construction_method: u8,
+        offset: usize,
+        length: usize
+    }
+
+    fn find_item_location(contents: &[u8], start: usize, end: usize,
+                          item_id: u32) -> Option<ItemLocation> {
+        let version = *contents.get(start)?;
+        let sizes = *contents.get(start + 4)?;
+        let base_sizes = *contents.get(start + 5)?;
+
+        let offset_size = (sizes >> 4) as usize;
+        let length_size = (sizes & 0x0F) as usize;
+        let base_offset_size = (base_sizes >> 4) as usize;
+        let index_size = if version == 1 || version == 2 { (base_sizes & 0x0F) as usize } else { 0 };
+
+        let mut offset = start + 6;
+
+        let item_count = if version < 2 {
+            offset += 2;
+            read_u16_be(contents, offset - 2)? as u32
+        } else {
+            offset += 4;
+            read_u32_be(contents, offset - 4)?
+        };
+
+        for _ in 0..item_count {
+            if offset >= end {
+                return None
+            }
+
+            let current_item_id = if version < 2 {
+                offset += 2;
+                read_u16_be(contents, offset - 2)? as u32
+            } else {
+                offset += 4;
+                read_u32_be(contents, offset - 4)?
+            };
+
+            let construction_method = if version == 1 || version == 2 {
+                offset += 2;
+                (read_u16_be(contents, offset - 2)? & 0x0F) as u8
+            } else { 0 };
+
+            // data reference index
+            offset += 2;
+
+            let base_offset = read_sized(contents, offset, base_offset_size)?;
+            offset += base_offset_size;
+
+            let extent_count = read_u16_be(contents, offset)?;
+            offset += 2;
+
+            let mut first_extent: Option<(usize, usize)> = None;
+
+            for _ in 0..extent_count {
+                offset += index_size;
+
+                let extent_offset = read_sized(contents, offset, offset_size)?;
+                offset += offset_size;
+
+                let extent_length = read_sized(contents, offset, length_size)?;
+                offset += length_size;
+
+                if first_extent.is_none() {
+                    first_extent = Some((extent_offset, extent_length));
+                }
+            }
+
+            if current_item_id == item_id {
+                // EXIF item is small, multiple extents aren't supported
+                let (extent_offset, extent_length) = first_extent?;
+
+                return Some(
+                    ItemLocation {
+                        construction_method,
+                        offset: base_offset.checked_add(extent_offset)?,
+                        length: extent_length
+                    }
+                )
+            }
+        }
+
+        None
+    }
+
+    /// Find ISO-BMFF box by type within `start..end`. Returns range of box content.
+    pub fn find_box(contents: &[u8], start: usize, end: usize,
+                box_type: &[u8]) -> Option<(usize, usize)> {
+        let end = end.min(contents.len());
+        let mut offset = start;
+
+        while offset + 8 <= end {
+            let size = read_u32_be(contents, offset)? as usize;
+            let current_type = &contents[offset + 4..offset + 8];
+
+            let (header_size, box_size) = match size {
+                0 => (8, end - offset),
+                1 => (16, read_u64_be(contents, offset + 8)? as usize),
+                _ => (8, size)
+            };
+
+            if box_size < header_size {
+                return None
+            }
+
+            let box_end = offset.checked_add(box_size)?;
+
+            if box_end > end {
+                return None
+            }
+
+            if current_type == box_type {
+                return Some((offset + header_size, box_end))
+            }
+
+            offset = box_end;
+        }
+
+        None
+    }
+
+    fn read_sized(contents: &[u8], offset: usize, size: usize) -> Option<usize> {
+        match size {
+            0 => Some(0),
+            4 => read_u32_be(contents, offset).map(|value| value as usize),
+            8 => read_u64_be(contents, offset).map(|value| value as usize),
+            _ => None
+        }
+    }
+
+    fn read_u16_be(contents: &[u8], offset: usize) -> Option<u16> {
+        let bytes = contents.get(offset..offset + 2)?;
+        Some(u16::from_be_bytes([bytes[0], bytes[1]]))
+    }
+
+    pub fn read_u32_be(contents: &[u8], offset: usize) -> Option<u32> {
+        let bytes = contents.get(offset..offset + 4)?;
+        Some(u32::from_be_bytes([bytes[0], bytes[1], bytes[2], bytes[3]]))
+    }
+
+    fn read_u32_le(contents: &[u8], offset: usize) -> Option<u32> {
+        let bytes = contents.get(offset..offset + 4)?;
+        Some(u32::from_le_bytes([bytes[0], bytes[1], bytes[2], bytes[3]]))
+    }
+
+    pub fn read_u64_be(contents: &[u8], offset: usize) -> Option<u64> {
+        let bytes = contents.get(offset..offset + 8)?;
+        let mut value = [0u8; 8];
+        value.copy_from_slice(bytes);
+        Some(u64::from_be_bytes(value))
+    }
+}
@@ -0,0 +1,192 @@
+#[cfg(test)]
+pub mod containers_tests {
+    use crate::containers::containers::{detect_container, find_exif_data, ImageContainer,
+                                        fix_raw_tiff_header, is_raw_file, sniff_content_type,
+                                        ContentType};
+    use std::path::Path;
+
+    const TIFF_DATA: &[u8] = b"II*\0\x08\0\0\0test";
+
+    #[test]
+    fn exif_should_be_found_in_png() {
+        let contents = build_png(TIFF_DATA);
+
+        assert_eq!(detect_container(&contents), Some(ImageContainer::Png));
+        assert_eq!(find_exif_data(&contents, ImageContainer::Png), Some(TIFF_DATA));
+    }
+
+    #[test]
+    fn exif_should_be_found_in_webp() {
+        let contents = build_webp(TIFF_DATA);
+
+        assert_eq!(detect_container(&contents), Some(ImageContainer::WebP));
+        assert_eq!(find_exif_data(&contents, ImageContainer::WebP), Some(TIFF_DATA));
+    }
+
+    #[test]
+    fn exif_should_be_found_in_heif() {
+        let contents = build_heif(TIFF_DATA);
+
+        assert_eq!(detect_container(&contents), Some(ImageContainer::Heif));
+        assert_eq!(find_exif_data(&contents, ImageContainer::Heif), Some(TIFF_DATA));
+    }
+
+    #[test]
+    fn none_should_be_returned_for_container_without_exif() {
+        let mut contents: Vec<u8> = b"\x89PNG\r\n\x1a\n".to_vec();
+        append_png_chunk(&mut contents, b"IHDR", &[0; 13]);
+        append_png_chunk(&mut contents, b"IEND", &[]);
+
+        assert!(find_exif_data(&contents, ImageContainer::Png).is_none());
+
+        let truncated_heif = &build_heif(TIFF_DATA)[..40];
+        assert!(find_exif_data(truncated_heif, ImageContainer::Heif).is_none());
+    }
+
+    #[test]
+    fn jpeg_should_not_be_detected_as_container() {
+        assert!(detect_container(b"\xff\xd8\xff\xe1\0\x10Exif\0\0").is_none());
+        assert!(detect_container(b"II*\0\x08\0\0\0").is_none());
+    }
+
+    #[test]
+    fn olympus_header_should_be_replaced_with_tiff_one() {
+        let mut contents = b"IIRO\x08\0\0\0".to_vec();
+        fix_raw_tiff_header(&mut contents);
+        assert_eq!(&contents[0..4], b"II*\0");
+
+        let mut contents = b"MMOR\0\0\0\x08".to_vec();
+        fix_raw_tiff_header(&mut contents);
+        assert_eq!(&contents[0..4], b"MM\0*");
+
+        let mut contents = b"II*\0\x08\0\0\0".to_vec();
+        fix_raw_tiff_header(&mut contents);
+        assert_eq!(&contents[0..4], b"II*\0");
+    }
+
+    #[test]
+    fn jpeg_should_be_found_in_raf() {
+        let jpeg_data: &[u8] = b"\xff\xd8\xff\xe1\0\x10Exif\0\0";
+
+        let mut contents = b"FUJIFILMCCD-RAW 0201FF383501".to_vec();
+        contents.resize(84, 0);
+        contents.extend_from_slice(&100u32.to_be_bytes());
+        contents.extend_from_slice(&(jpeg_data.len() as u32).to_be_bytes());
+        contents.resize(100, 0);
+        contents.extend_from_slice(jpeg_data);
+
+        assert_eq!(detect_container(&contents), Some(ImageContainer::Raf));
+        assert_eq!(find_exif_data(&contents, ImageContainer::Raf), Some(jpeg_data));
+    }
+
+    #[test]
+    fn raw_files_should_be_detected_by_extension() {
+        assert!(is_raw_file(Path::new("IMG_0001.CR2")));
+        assert!(is_raw_file(Path::new("/photos/DSC_0001.nef")));
+        assert!(is_raw_file(Path::new("P0001.ORF")));
+        assert!(!is_raw_file(Path::new("IMG_0001.jpg")));
+    }
+
+    #[test]
+    fn content_type_should_be_detected_by_magic_bytes() {
+        assert_eq!(sniff_content_type(b"\xff\xd8\xff\xe1\0\x10Exif\0\0"), Some(ContentType::Image));
+        assert_eq!(sniff_content_type(b"IIRO\x08\0\0\0\0\0\0\0"), Some(ContentType::Image));
+        assert_eq!(sniff_content_type(&build_png(TIFF_DATA)), Some(ContentType::Image));
+        assert_eq!(sniff_content_type(&build_heif(TIFF_DATA)), Some(ContentType::Image));
+        assert_eq!(sniff_content_type(b"\0\0\0\x14ftypisom\0\0\0\0isom"), Some(ContentType::Video));
+        assert_eq!(sniff_content_type(b"not an image at all"), None);
+        assert_eq!(sniff_content_type(b"short"), None);
+    }
+
+    pub fn build_png(tiff_data: &[u8]) -> Vec<u8> {
+        let mut results: Vec<u8> = b"\x89PNG\r\n\x1a\n".to_vec();
+
+        append_png_chunk(&mut results, b"IHDR", &[0; 13]);
+        append_png_chunk(&mut results, b"eXIf", tiff_data);
+        append_png_chunk(&mut results, b"IEND", &[]);
+
+        results
+    }
+
+    pub fn build_webp(tiff_data: &[u8]) -> Vec<u8> {
+        let mut chunks: Vec<u8> = Vec::new();
+
+        append_webp_chunk(&mut chunks, b"VP8X", &[0; 10]);
+
+        let mut exif_data = b"Exif\0\0".to_vec();
+        exif_data.extend_from_slice(tiff_data);
+        append_webp_chunk(&mut chunks, b"EXIF", &exif_data);
+
+        let mut results: Vec<u8> = b"RIFF".to_vec();
+        results.extend_from_slice(&((chunks.len() + 4) as u32).to_le_bytes());
+        results.extend_from_slice(b"WEBP");
+        results.extend_from_slice(&chunks);
+
+        results
+    }
+
+    /// HEIF with single `Exif` item stored in `mdat`.
+    pub fn build_heif(tiff_data: &[u8]) -> Vec<u8> {
+        let mut results = build_box(b"ftyp", b"heic\0\0\0\0mif1heic");
+
+        let mut infe: Vec<u8> = vec![2, 0, 0, 0];
+        infe.extend_from_slice(&1u16.to_be_bytes());
+        infe.extend_from_slice(&0u16.to_be_bytes());
+        infe.extend_from_slice(b"Exif\0");
+
+        let mut iinf: Vec<u8> = vec![0, 0, 0, 0];
+        iinf.extend_from_slice(&1u16.to_be_bytes());
+        iinf.extend_from_slice(&build_box(b"infe", &infe));
+
+        let mut item_data: Vec<u8> = 6u32.to_be_bytes().to_vec();
+        item_data.extend_from_slice(b"Exif\0\0");
+        item_data.extend_from_slice(tiff_data);
+
+        // iloc size is fixed, so item offset can be calculated before
+        let iloc_size = 8 + 4 + 2 + 2 + 2 + 2 + 2 + 4 + 4;
+        let meta_size = 8 + 4 + 8 + iinf.len() + iloc_size;
+        let item_offset = results.len() + meta_size + 8;
+
+        let mut iloc: Vec<u8> = vec![0, 0, 0, 0, 0x44, 0x00];
+        iloc.extend_from_slice(&1u16.to_be_bytes());
+        iloc.extend_from_slice(&1u16.to_be_bytes());
+        iloc.extend_from_slice(&0u16.to_be_bytes());
+        iloc.extend_from_slice(&1u16.to_be_bytes());
+        iloc.extend_from_slice(&(item_offset as u32).to_be_bytes());
+        iloc.extend_from_slice(&(item_data.len() as u32).to_be_bytes());
+
+        let mut meta: Vec<u8> = vec![0, 0, 0, 0];
+        meta.extend_from_slice(&build_box(b"iinf", &iinf));
+        meta.extend_from_slice(&build_box(b"iloc", &iloc));
+
+        results.extend_from_slice(&build_box(b"meta", &meta));
+        results.extend_from_slice(&build_box(b"mdat", &item_data));
+
+        results
+    }
+
+    fn append_png_chunk(contents: &mut Vec<u8>, chunk_type: &[u8], data: &[u8]) {
+        contents.extend_from_slice(&(data.len() as u32).to_be_bytes());
+        contents.extend_from_slice(chunk_type);
+        contents.extend_from_slice(data);
+        // crc isn't checked
+        contents.extend_from_slice(&[0; 4]);
+    }
+
+    fn append_webp_chunk(contents: &mut Vec<u8>, chunk_type: &[u8], data: &[u8]) {
+        contents.extend_from_slice(chunk_type);
+        contents.extend_from_slice(&(data.len() as u32).to_le_bytes());
+        contents.extend_from_slice(data);
+
+        if data.len() % 2 == 1 {
+            contents.push(0);
+        }
+    }
+
+    fn build_box(box_type: &[u8], data: &[u8]) -> Vec<u8> {
+        let mut results: Vec<u8> = ((data.len() + 8) as u32).to_be_bytes().to_vec();
+        results.extend_from_slice(box_type);
+        results.extend_from_slice(data);
+        results
+    }
+}
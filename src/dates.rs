@@ -0,0 +1,346 @@
+pub mod dates {
+    use chrono::{NaiveDate, NaiveTime, NaiveDateTime, Local, TimeZone, Duration, FixedOffset, Utc};
+    use std::{fs, io};
+    use std::convert::TryFrom;
+    use std::path::Path;
+    use std::time::{SystemTime, UNIX_EPOCH};
+    use crate::domain::domain::{NoExifConfig, ExifConfig, DateSource, TargetTimeZone, DateIssue,
+                                MetadataError, FileTimestamp};
+    use crate::exif::exif::get_exif_info_from_file;
+    use crate::video::video::{is_video_file, get_video_info_from_file};
+    use crate::time_shift::time_shift::format_time_shift;
+    use crate::path_parser::path_parser::{get_dates_from_path, get_path_without_dir_names};
+
+    /// Resolved date of file.
+    #[derive(Clone)]
+    pub struct FileDate {
+        pub date: NaiveDate,
+
+        /// Unknown for dates extracted from path or forced year.
+        /// Might contain sub-seconds.
+        pub time: Option<NaiveTime>,
+
+        pub source: DateSource
+    }
+
+    /// Result of file date resolution.
+    #[derive(Clone)]
+    pub struct FileDateInfo {
+        /// `None` if date can't be determined
+        pub file_date: Option<FileDate>,
+
+        pub camera_model: Option<String>,
+
+        /// EXIF or video meta-data can't be read
+        pub metadata_error: Option<String>,
+
+        /// Why date hasn't been taken from meta-data, `None` if it has been
+        pub date_issue: Option<DateIssue>
+    }
+
+    /// Resolve file date: EXIF (tags in priority order) or video container metadata,
+    /// then path or forced year.
+    pub fn resolve_file_date(file_path: &Path,
+                             no_exif_config: &NoExifConfig,
+                             exif_config: &ExifConfig) -> FileDateInfo {
+        if is_video_file(file_path) {
+            return resolve_video_date(file_path, no_exif_config, exif_config)
+        }
+
+        match get_exif_info_from_file(file_path, &exif_config.date_tags) {
+            Ok(exif_info) => {
+                let date_issue = get_missing_date_issue(
+                    exif_info.date_created.is_some(), &exif_info.invalid_dates
+                );
+
+                let file_date = match exif_info.date_created {
+                    Some(exif_date) => {
+                        let datetime = apply_time_shift(
+                            exif_date.datetime, file_path,
+                            exif_info.camera_model.as_deref(), exif_config
+                        );
+
+                        let file_datetime = normalize_datetime(
+                            datetime, exif_date.utc_offset, &exif_config.timezone
+                        );
+
+                        Some(
+                            FileDate {
+                                date: file_datetime.date(),
+                                time: Some(file_datetime.time()),
+                                source: DateSource::Exif(exif_date.tag)
+                            }
+                        )
+                    }
+                    None => {
+                        warn!(
+                            "file '{}' doesn't contain date in EXIF meta-data",
+                            file_path.display()
+                        );
+
+                        get_file_date_without_exif(file_path, no_exif_config, exif_config)
+                    }
+                };
+
+                FileDateInfo {
+                    file_date, camera_model: exif_info.camera_model, metadata_error: None, date_issue
+                }
+            }
+            Err(e) => {
+                warn!("file '{}' doesn't contain EXIF meta-data", file_path.display());
+
+                FileDateInfo {
+                    file_date: get_file_date_without_exif(file_path, no_exif_config, exif_config),
+                    camera_model: None,
+                    date_issue: Some(get_metadata_error_issue(&e)),
+                    metadata_error: Some(e.to_string())
+                }
+            }
+        }
+    }
+
+    fn resolve_video_date(file_path: &Path,
+                          no_exif_config: &NoExifConfig,
+                          exif_config: &ExifConfig) -> FileDateInfo {
+        match get_video_info_from_file(file_path) {
+            Ok(video_info) => {
+                let date_issue = get_missing_date_issue(
+                    video_info.date_created.is_some(), &video_info.invalid_dates
+                );
+
+                let file_date = match video_info.date_created {
+                    Some(video_date) => {
+                        let datetime = apply_time_shift(
+                            video_date.datetime, file_path,
+                            video_info.camera_model.as_deref(), exif_config
+                        );
+
+                        // header dates are kept in UTC unless time zone is set, like EXIF dates
+                        let file_datetime = normalize_datetime(
+                            datetime, video_date.utc_offset, &exif_config.timezone
+                        );
+
+                        Some(
+                            FileDate {
+                                date: file_datetime.date(),
+                                time: Some(file_datetime.time()),
+                                source: DateSource::Video(video_date.tag)
+                            }
+                        )
+                    }
+                    None => {
+                        warn!("file '{}' doesn't contain date in video meta-data",
+                              file_path.display());
+                        get_file_date_without_exif(file_path, no_exif_config, exif_config)
+                    }
+                };
+
+                FileDateInfo {
+                    file_date, camera_model: video_info.camera_model, metadata_error: None, date_issue
+                }
+            }
+            Err(e) => {
+                warn!("unable to read video meta-data from file '{}'", file_path.display());
+
+                FileDateInfo {
+                    file_date: get_file_date_without_exif(file_path, no_exif_config, exif_config),
+                    camera_model: None,
+                    date_issue: Some(get_metadata_error_issue(&e)),
+                    metadata_error: Some(e.to_string())
+                }
+            }
+        }
+    }
+
+    fn get_missing_date_issue(has_date: bool, invalid_dates: &Vec<String>) -> Option<DateIssue> {
+        if has_date {
+            None
+
+        } else if invalid_dates.is_empty() {
+            Some(DateIssue::NoDate)
+
+        } else {
+            Some(DateIssue::InvalidDate)
+        }
+    }
+
+    fn get_metadata_error_issue(error: &MetadataError) -> DateIssue {
+        match error {
+            MetadataError::Unreadable(_) => DateIssue::Unreadable,
+            MetadataError::NoMetadata(_) => DateIssue::NoMetadata
+        }
+    }
+
+    fn apply_time_shift(datetime: NaiveDateTime, file_path: &Path,
+                        camera_model: Option<&str>, exif_config: &ExifConfig) -> NaiveDateTime {
+        match exif_config.time_shift.get_time_shift(file_path, camera_model) {
+            Some(shift) => match datetime.checked_add_signed(shift) {
+                Some(shifted_datetime) => {
+                    info!("time shift {} has been applied: '{}' > '{}'",
+                          format_time_shift(&shift), datetime, shifted_datetime);
+
+                    shifted_datetime
+                }
+                None => {
+                    warn!("time shift {} can't be applied to '{}' of '{}', date is out of range",
+                          format_time_shift(&shift), datetime, file_path.display());
+
+                    datetime
+                }
+            }
+            None => datetime
+        }
+    }
+
+    /// Convert camera local time to target time zone. Time is left as is if offset is unknown.
+    fn normalize_datetime(datetime: NaiveDateTime, utc_offset: Option<FixedOffset>,
+                          timezone: &Option<TargetTimeZone>) -> NaiveDateTime {
+        match timezone {
+            Some(timezone) => {
+                match utc_offset {
+                    Some(utc_offset) => {
+                        let utc_datetime = datetime -
+                            Duration::seconds(utc_offset.local_minus_utc() as i64);
+
+                        let result = match timezone {
+                            TargetTimeZone::Utc => utc_datetime,
+                            TargetTimeZone::Local =>
+                                Local.from_utc_datetime(&utc_datetime).naive_local(),
+                            TargetTimeZone::Fixed(offset) =>
+                                utc_datetime + Duration::seconds(offset.local_minus_utc() as i64)
+                        };
+
+                        info!("time has been converted from '{}' ({}) to '{}' ({})",
+                              datetime, utc_offset, result, timezone);
+
+                        result
+                    }
+                    None => {
+                        warn!("offset from UTC is unknown, time isn't converted to '{}'", timezone);
+                        datetime
+                    }
+                }
+            }
+            None => datetime
+        }
+    }
+
+    /// Date from path, then filesystem timestamp (if fallback is set), then forced year.
+    fn get_file_date_without_exif(file_path: &Path, no_exif_config: &NoExifConfig,
+                                  exif_config: &ExifConfig) -> Option<FileDate> {
+        if no_exif_config.extract_dates_from_path && !no_exif_config.force_year {
+            if let Some(file_date) = get_file_date_from_path(file_path, no_exif_config) {
+                return Some(file_date)
+            }
+        }
+
+        if let Some(fallback) = no_exif_config.fallback {
+            match get_file_timestamp(file_path, fallback) {
+                Ok(utc_datetime) => {
+                    warn!("date of '{}' has been taken from filesystem timestamp '{}'",
+                          file_path.display(), fallback);
+
+                    let timezone = Some(exif_config.timezone.unwrap_or(TargetTimeZone::Local));
+
+                    let file_datetime = normalize_datetime(
+                        utc_datetime, FixedOffset::east_opt(0), &timezone
+                    );
+
+                    return Some(
+                        FileDate {
+                            date: file_datetime.date(),
+                            time: Some(file_datetime.time()),
+                            source: DateSource::FileTime(fallback)
+                        }
+                    )
+                }
+                Err(e) => warn!("unable to get timestamp '{}' of '{}': {}",
+                                fallback, file_path.display(), e)
+            }
+        }
+
+        if no_exif_config.extract_dates_from_path && no_exif_config.force_year {
+            let local_dt = Local.ymd(no_exif_config.year, 1, 1)
+                .and_hms_milli(9, 10, 11, 12);
+            let file_date = local_dt.naive_local().date();
+
+            Some(
+                FileDate { date: file_date, time: None, source: DateSource::ForcedYear }
+            )
+
+        } else {
+            None
+        }
+    }
+
+    fn get_file_date_from_path(file_path: &Path, no_exif_config: &NoExifConfig) -> Option<FileDate> {
+        // dates consist of ASCII digits, so undecodable characters can be replaced
+        let sanitized_path: String = get_path_without_dir_names(
+            &file_path.to_string_lossy(),
+            &no_exif_config.skip_dir_names_for_date_extract
+        );
+
+        let extracted_dates = get_dates_from_path(&sanitized_path);
+
+        if !extracted_dates.is_empty() {
+            let (file_date, pattern) = extracted_dates.last().unwrap();
+
+            Some(
+                FileDate { date: *file_date, time: None, source: DateSource::Path(*pattern) }
+            )
+
+        } else {
+            info!("unable to determine file date because file path doesn't \
+               contain any information about date");
+
+            None
+        }
+    }
+
+    /// Filesystem timestamp in UTC.
+    fn get_file_timestamp(file_path: &Path, timestamp: FileTimestamp) -> Result<NaiveDateTime, io::Error> {
+        let metadata = fs::metadata(file_path)?;
+
+        match timestamp {
+            FileTimestamp::Modified => get_utc_datetime(metadata.modified()?),
+            FileTimestamp::Changed => get_changed_time(&metadata),
+            FileTimestamp::Birth => get_utc_datetime(metadata.created()?)
+        }
+    }
+
+    fn get_utc_datetime(time: SystemTime) -> Result<NaiveDateTime, io::Error> {
+        let (seconds, nanos) = match time.duration_since(UNIX_EPOCH) {
+            Ok(duration) => (i64::try_from(duration.as_secs()).ok(), duration.subsec_nanos()),
+            Err(e) => {
+                let duration = e.duration();
+                let seconds = i64::try_from(duration.as_secs()).ok().map(|seconds| -seconds);
+
+                match duration.subsec_nanos() {
+                    0 => (seconds, 0),
+                    nanos => (seconds.and_then(|seconds| seconds.checked_sub(1)), 1_000_000_000 - nanos)
+                }
+            }
+        };
+
+        seconds.and_then(|seconds| get_timestamp_datetime(seconds, nanos))
+            .ok_or_else(|| io::Error::new(io::ErrorKind::InvalidData, "timestamp is out of range"))
+    }
+
+    fn get_timestamp_datetime(seconds: i64, nanos: u32) -> Option<NaiveDateTime> {
+        Utc.timestamp_opt(seconds, nanos).single().map(|datetime| datetime.naive_utc())
+    }
+
+    #[cfg(unix)]
+    fn get_changed_time(metadata: &fs::Metadata) -> Result<NaiveDateTime, io::Error> {
+        use std::os::unix::fs::MetadataExt;
+
+        get_timestamp_datetime(metadata.ctime(), metadata.ctime_nsec() as u32)
+            .ok_or_else(|| io::Error::new(io::ErrorKind::InvalidData, "timestamp is out of range"))
+    }
+
+    /// No status change time, creation time is the closest
+    #[cfg(not(unix))]
+    fn get_changed_time(metadata: &fs::Metadata) -> Result<NaiveDateTime, io::Error> {
+        get_utc_datetime(metadata.created()?)
+    }
+}
@@ -1,67 +1,310 @@
 pub mod diag {
-    use std::io;
-    use crate::files::files::get_files_from_path;
-    use crate::path_parser::path_parser::{get_dates_from_path, get_path_without_dir_names};
-    use crate::exif::exif::get_date_created_from_file_exif;
+    use std::{io, fmt};
+    use std::path::{Path, PathBuf};
+    use std::collections::BTreeMap;
+    use serde_json::{json, Value};
+    use crate::files::files::walk_files;
+    use crate::domain::domain::{FileFilter, NoExifConfig, ExifConfig, DateSource, DiagConfig,
+                                DateIssue};
+    use crate::dates::dates::{resolve_file_date, FileDate};
+    use crate::commands::commands::get_relative_file_path;
+
+    const CSV_HEADER: &str = "path,date,date_source,metadata_error,destination,issues";
+
+    /// Why file date is missing or hasn't been taken from meta-data.
+    #[derive(Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Debug)]
+    pub enum IssueCategory {
+        UnreadableFile,
+        NoExif,
+        ExifWithoutDate,
+        UnparsableDate,
+        DateOnlyFromPath,
+        DateOnlyFromForcedYear,
+        DateOnlyFromFileTime
+    }
+
+    impl IssueCategory {
+        /// Name for JSON and CSV reports
+        pub fn name(&self) -> &'static str {
+            match self {
+                IssueCategory::UnreadableFile => "unreadable",
+                IssueCategory::NoExif => "no-exif",
+                IssueCategory::ExifWithoutDate => "no-date",
+                IssueCategory::UnparsableDate => "invalid-date",
+                IssueCategory::DateOnlyFromPath => "path-date",
+                IssueCategory::DateOnlyFromForcedYear => "forced-year",
+                IssueCategory::DateOnlyFromFileTime => "file-time"
+            }
+        }
+    }
+
+    impl fmt::Display for IssueCategory {
+        fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+            let description = match self {
+                IssueCategory::UnreadableFile => "unreadable file",
+                IssueCategory::NoExif => "no EXIF",
+                IssueCategory::ExifWithoutDate => "EXIF without date",
+                IssueCategory::UnparsableDate => "unparsable date",
+                IssueCategory::DateOnlyFromPath => "date only from path",
+                IssueCategory::DateOnlyFromForcedYear => "date only from forced year",
+                IssueCategory::DateOnlyFromFileTime => "date only from filesystem timestamp"
+            };
+
+            write!(f, "{}", description)
+        }
+    }
+
+    /// Date resolution result for single file.
+    pub struct DiagRecord {
+        pub file_path: PathBuf,
+
+        /// `None` if date can't be determined
+        pub file_date: Option<FileDate>,
+
+        /// EXIF or video meta-data can't be read
+        pub metadata_error: Option<String>,
+
+        /// Destination path relative to destination directory, if date is known
+        pub destination: Option<PathBuf>,
+
+        pub issues: Vec<IssueCategory>
+    }
+
+    impl DiagRecord {
+        /// Date with time if known: 2019-07-13T13:12:57
+        pub fn get_date_string(&self) -> Option<String> {
+            self.file_date.as_ref().map(|file_date|
+                match file_date.time {
+                    Some(time) => format!("{}T{}", file_date.date.format("%Y-%m-%d"),
+                                          time.format("%H:%M:%S")),
+                    None => file_date.date.format("%Y-%m-%d").to_string()
+                }
+            )
+        }
+
+        pub fn get_date_source_string(&self) -> String {
+            match &self.file_date {
+                Some(file_date) => file_date.source.to_string(),
+                None => String::from("none")
+            }
+        }
+
+        /// Issue names separated by ';'
+        pub fn get_issues_string(&self) -> String {
+            self.issues.iter().map(|issue| issue.name()).collect::<Vec<&str>>().join(";")
+        }
+    }
 
     pub struct DiagReport {
         pub files_total: usize,
-        pub files_with_issues: Vec<String>
+
+        /// Files without date
+        pub files_with_issues: Vec<PathBuf>,
+
+        /// Issue category > files count, file might have several issues
+        pub issue_counts: BTreeMap<IssueCategory, usize>,
+
+        /// File names which can't be represented as UTF-8
+        pub files_with_invalid_names: Vec<PathBuf>,
+
+        /// How dates have been derived: date source > files count
+        pub date_sources: BTreeMap<DateSource, usize>,
+
+        /// All processed files in order of discovery, empty unless `keep_records` is set
+        pub records: Vec<DiagRecord>,
+
+        /// Files and directories skipped by exclusion rules
+        pub excluded_paths: usize,
+
+        /// Directories and entries which couldn't be read, traversal has been continued
+        pub traversal_errors: Vec<String>,
+
+        /// Symbolic links with missing target
+        pub broken_links: Vec<PathBuf>
     }
 
-    pub fn diag_path(src_path: &str, file_ext_filter: &Vec<String>,
-             extract_dates_from_path: bool, skip_dir_names_for_date_extract: Vec<String>,
-             on_progress: fn(total: usize, current_index: usize,
+    impl DiagReport {
+        pub fn to_json(&self) -> Value {
+            json!({
+                "files_total": self.files_total,
+                "files": self.records.iter().map(|record| json!({
+                    "path": record.file_path.to_string_lossy(),
+                    "date": record.get_date_string(),
+                    "date_source": record.get_date_source_string(),
+                    "metadata_error": record.metadata_error,
+                    "destination": record.destination.as_ref()
+                                                     .map(|destination| destination.to_string_lossy()),
+                    "issues": record.issues.iter().map(|issue| issue.name()).collect::<Vec<&str>>()
+                })).collect::<Vec<Value>>(),
+                "issues": self.issue_counts.iter()
+                    .map(|(issue, files_count)| (issue.name().to_string(), json!(files_count)))
+                    .collect::<serde_json::Map<String, Value>>(),
+                "date_sources": self.date_sources.iter()
+                    .map(|(date_source, files_count)| (date_source.to_string(), json!(files_count)))
+                    .collect::<serde_json::Map<String, Value>>(),
+                "files_with_invalid_names": self.files_with_invalid_names.iter()
+                    .map(|file_path| format!("{:?}", file_path))
+                    .collect::<Vec<String>>(),
+                "excluded_paths": self.excluded_paths,
+                "traversal_errors": self.traversal_errors,
+                "broken_links": self.broken_links.iter()
+                                                 .map(|file_path| file_path.to_string_lossy())
+                                                 .collect::<Vec<_>>()
+            })
+        }
+
+        /// One row per file, values are quoted if needed (RFC 4180).
+        pub fn to_csv(&self) -> String {
+            let mut results = String::from(CSV_HEADER);
+            results.push('\n');
+
+            for record in self.records.iter() {
+                let values = [
+                    record.file_path.to_string_lossy().to_string(),
+                    record.get_date_string().unwrap_or_default(),
+                    record.get_date_source_string(),
+                    record.metadata_error.clone().unwrap_or_default(),
+                    record.destination.as_ref()
+                                      .map(|destination| destination.to_string_lossy().to_string())
+                                      .unwrap_or_default(),
+                    record.get_issues_string()
+                ];
+
+                let row: Vec<String> = values.iter().map(|value| get_csv_value(value)).collect();
+
+                results.push_str(&row.join(","));
+                results.push('\n');
+            }
+
+            results
+        }
+    }
+
+    pub fn diag_path(src_path: &Path, file_filter: &FileFilter,
+             no_exif_config: &NoExifConfig, exif_config: &ExifConfig, diag_config: &DiagConfig,
+             on_progress: fn(discovered: usize, processed: usize,
                              with_issue: usize)) -> Result<DiagReport, io::Error> {
-        info!("path '{}' diagnostics", src_path);
-
-        match get_files_from_path(src_path, file_ext_filter) {
-            Ok(files) => {
-                let mut results: Vec<String> = Vec::new();
-
-                for (index, file_path_str) in files.iter().enumerate() {
-                    info!("processing file '{}'", file_path_str);
-
-                    match get_date_created_from_file_exif(&file_path_str) {
-                        Ok(date_created) => {
-                            match date_created {
-                                Some(_) => {}
-                                None => {
-
-                                    if extract_dates_from_path {
-                                        let sanitized_path: String = get_path_without_dir_names(
-                                            file_path_str,
-                                            &skip_dir_names_for_date_extract
-                                        );
-
-                                        let extracted_dates = get_dates_from_path(&sanitized_path);
-
-                                        if extracted_dates.is_empty() {
-                                            info!("added '{}'", file_path_str);
-                                            results.push(String::from(file_path_str))
-                                        }
-
-                                    } else {
-                                        info!("added '{}'", file_path_str);
-                                        results.push(String::from(file_path_str))
-                                    }
-                                }
-                            }
-                        }
-                        Err(_) => {}
-                    }
-
-                    on_progress(files.len(), index, results.len())
+        info!("path '{}' diagnostics", src_path.display());
+        info!("file filter: {}", file_filter.to_string());
+        info!("no exif config: {}", no_exif_config.to_string());
+        info!("exif config: {}", exif_config.to_string());
+        info!("diag config: {}", diag_config.to_string());
+
+        let mut walker = match walk_files(src_path, file_filter) {
+            Ok(walker) => walker,
+            Err(e) => {
+                error!("unable to get files from path '{}': {}", src_path.display(), e);
+                return Err(e)
+            }
+        };
+
+        let mut results: Vec<PathBuf> = Vec::new();
+        let mut invalid_names: Vec<PathBuf> = Vec::new();
+        let mut date_sources: BTreeMap<DateSource, usize> = BTreeMap::new();
+        let mut issue_counts: BTreeMap<IssueCategory, usize> = BTreeMap::new();
+        let mut records: Vec<DiagRecord> = Vec::new();
+        let mut traversal_errors: Vec<String> = Vec::new();
+
+        let mut processed_files: usize = 0;
+
+        while let Some(item) = walker.next() {
+            let file_path = match item {
+                Ok(file_path) => file_path,
+                Err(e) => {
+                    error!("traversal error: {}", e);
+                    traversal_errors.push(e);
+                    continue
                 }
+            };
+
+            info!("processing file '{}'", file_path.display());
 
-                Ok(
-                    DiagReport { files_total: files.len(), files_with_issues: results }
-                )
+            if file_path.file_name().and_then(|name| name.to_str()).is_none() {
+                warn!("file name isn't valid UTF-8: {:?}", file_path);
+                invalid_names.push(file_path.clone());
             }
-            Err(e) => {
-                error!("unable to get files from path '{}': {}", src_path, e);
-                Err(e)
+
+            let file_date_info = resolve_file_date(&file_path, no_exif_config, exif_config);
+
+            let destination = match &file_date_info.file_date {
+                Some(file_date) => {
+                    info!("date source: {}", file_date.source);
+                    *date_sources.entry(file_date.source).or_insert(0) += 1;
+
+                    file_path.file_name().filter(|_| diag_config.keep_records).map(|file_name|
+                        PathBuf::from(get_relative_file_path(
+                            file_date, file_date_info.camera_model.as_deref(), file_name,
+                            &diag_config.template, &diag_config.month_names,
+                            diag_config.with_milliseconds
+                        ))
+                    )
+                }
+                None => {
+                    info!("added '{}'", file_path.display());
+                    results.push(file_path.clone());
+                    None
+                }
+            };
+
+            let issues = get_issues(file_date_info.date_issue,
+                                    file_date_info.file_date.as_ref().map(|file_date| file_date.source));
+
+            for issue in issues.iter() {
+                info!("issue: {}", issue);
+                *issue_counts.entry(*issue).or_insert(0) += 1;
             }
+
+            if diag_config.keep_records {
+                records.push(DiagRecord {
+                    file_path,
+                    file_date: file_date_info.file_date,
+                    metadata_error: file_date_info.metadata_error,
+                    destination,
+                    issues
+                });
+            }
+
+            processed_files += 1;
+            on_progress(walker.discovered_files, processed_files, results.len())
+        }
+
+        Ok(
+            DiagReport {
+                files_total: walker.discovered_files, files_with_issues: results, issue_counts,
+                files_with_invalid_names: invalid_names, date_sources, records,
+                excluded_paths: walker.excluded_paths, traversal_errors,
+                broken_links: walker.broken_links
+            }
+        )
+    }
+
+    fn get_issues(date_issue: Option<DateIssue>, date_source: Option<DateSource>) -> Vec<IssueCategory> {
+        let mut results: Vec<IssueCategory> = Vec::new();
+
+        match date_issue {
+            Some(DateIssue::Unreadable) => results.push(IssueCategory::UnreadableFile),
+            Some(DateIssue::NoMetadata) => results.push(IssueCategory::NoExif),
+            Some(DateIssue::NoDate) => results.push(IssueCategory::ExifWithoutDate),
+            Some(DateIssue::InvalidDate) => results.push(IssueCategory::UnparsableDate),
+            None => {}
+        }
+
+        match date_source {
+            Some(DateSource::Path(_)) => results.push(IssueCategory::DateOnlyFromPath),
+            Some(DateSource::ForcedYear) => results.push(IssueCategory::DateOnlyFromForcedYear),
+            Some(DateSource::FileTime(_)) => results.push(IssueCategory::DateOnlyFromFileTime),
+            _ => {}
+        }
+
+        results
+    }
+
+    fn get_csv_value(value: &str) -> String {
+        if value.contains(|c| c == ',' || c == '"' || c == '\n' || c == '\r') {
+            format!("\"{}\"", value.replace('"', "\"\""))
+
+        } else {
+            String::from(value)
         }
     }
 }
@@ -0,0 +1,195 @@
+#[cfg(test)]
+pub mod diag_tests {
+    use std::{env, fs};
+    use std::path::{Path, PathBuf};
+    use crate::diag::diag::{diag_path, IssueCategory};
+    use crate::domain::domain::{FileFilter, NoExifConfig, ExifConfig, DiagConfig};
+    use crate::exclusions::exclusions::ExcludeRules;
+    use crate::exif::exif::DEFAULT_DATE_TAGS;
+    use crate::get_extension_filters;
+    use crate::template::template::{parse_template, DEFAULT_TEMPLATE};
+    use crate::locale::locale::{get_month_names, DEFAULT_LOCALE};
+    use crate::time_shift::time_shift::TimeShiftConfig;
+
+    const SOURCE_DIR_NAME: &str = "img-src";
+
+    #[test]
+    fn records_should_contain_date_source_and_destination() {
+        let src_dir = get_test_dir("records_should_contain_date_source_and_destination");
+
+        fs::copy(format!("{}/20201010/IMG_20201010_120947.jpg", SOURCE_DIR_NAME),
+                 src_dir.join("IMG_0001, copy.jpg")).unwrap();
+        fs::copy(format!("{}/wrong-exif/wrong-exif.jpg", SOURCE_DIR_NAME),
+                 src_dir.join("wrong-exif.jpg")).unwrap();
+
+        let no_exif_config = get_no_exif_config(false);
+
+        let report = diag_path(&src_dir, &get_file_filter(), &no_exif_config,
+                               &get_exif_config(), &get_diag_config(), show_progress).unwrap();
+
+        assert_eq!(report.records.len(), 2);
+
+        let record = report.records.iter()
+            .find(|record| record.file_date.is_some()).unwrap();
+
+        assert_eq!(record.get_date_string().unwrap(), "2020-10-10T12:09:47");
+        assert_eq!(record.get_date_source_string(), "exif:DateTimeOriginal");
+        assert_eq!(record.destination.as_ref().unwrap(),
+                   Path::new("2020/Октябрь/2020-10-10__12-09-47__IMG_0001, copy.jpg"));
+
+        let csv = report.to_csv();
+        let lines: Vec<&str> = csv.lines().collect();
+
+        assert_eq!(lines.len(), 3);
+        assert_eq!(lines[0], "path,date,date_source,metadata_error,destination,issues");
+        assert!(lines.contains(&format!(
+            "\"{}\",2020-10-10T12:09:47,exif:DateTimeOriginal,,\
+             \"2020/Октябрь/2020-10-10__12-09-47__IMG_0001, copy.jpg\",",
+            src_dir.join("IMG_0001, copy.jpg").display()
+        ).as_str()));
+        assert!(lines.contains(
+            &format!("{},,none,,,invalid-date", src_dir.join("wrong-exif.jpg").display()).as_str()
+        ));
+
+        let json = report.to_json();
+        assert_eq!(json["files"].as_array().unwrap().len(), 2);
+        assert_eq!(json["date_sources"]["exif:DateTimeOriginal"], 1);
+
+        fs::remove_dir_all(&src_dir).unwrap();
+    }
+
+    #[test]
+    fn issues_should_be_counted_by_category() {
+        let src_dir = get_test_dir("issues_should_be_counted_by_category");
+
+        fs::create_dir_all(src_dir.join("20130517")).unwrap();
+
+        fs::copy(format!("{}/exif-without-date/20130517/Subfolder/manga__berserk__forest.jpg",
+                         SOURCE_DIR_NAME),
+                 src_dir.join("20130517/forest.jpg")).unwrap();
+        fs::copy(format!("{}/wrong-exif/wrong-exif.jpg", SOURCE_DIR_NAME),
+                 src_dir.join("wrong-exif.jpg")).unwrap();
+        fs::write(src_dir.join("without-exif.jpg"), "not a jpeg").unwrap();
+
+        let mut no_exif_config = get_no_exif_config(true);
+
+        let report = diag_path(&src_dir, &get_file_filter(), &no_exif_config,
+                               &get_exif_config(), &get_diag_config(), show_progress).unwrap();
+
+        assert_eq!(report.files_total, 3);
+        assert_eq!(report.files_with_issues.len(), 2);
+
+        let get_issues = |file_name: &str| report.records.iter()
+            .find(|record| record.file_path.ends_with(file_name))
+            .map(|record| record.issues.clone()).unwrap();
+
+        assert_eq!(get_issues("forest.jpg"),
+                   vec![IssueCategory::ExifWithoutDate, IssueCategory::DateOnlyFromPath]);
+        assert_eq!(get_issues("wrong-exif.jpg"), vec![IssueCategory::UnparsableDate]);
+        assert_eq!(get_issues("without-exif.jpg"), vec![IssueCategory::NoExif]);
+        assert_eq!(report.issue_counts.get(&IssueCategory::UnreadableFile), None);
+
+        let forest_record = report.records.iter()
+            .find(|record| record.file_path.ends_with("forest.jpg")).unwrap();
+        assert_eq!(forest_record.get_date_source_string(), "path:YYYYMMDD");
+        assert!(report.to_csv().contains(",2013-05-17,path:YYYYMMDD,"));
+
+        let json = report.to_json();
+        assert_eq!(json["issues"]["no-exif"], 1);
+        assert_eq!(json["issues"]["path-date"], 1);
+        assert_eq!(json["date_sources"]["path:YYYYMMDD"], 1);
+
+        no_exif_config.force_year = true;
+        no_exif_config.year = 2015;
+
+        let report = diag_path(&src_dir, &get_file_filter(), &no_exif_config,
+                               &get_exif_config(), &get_diag_config(), show_progress).unwrap();
+
+        assert!(report.files_with_issues.is_empty());
+        assert_eq!(report.issue_counts.get(&IssueCategory::DateOnlyFromForcedYear), Some(&3));
+
+        fs::remove_dir_all(&src_dir).unwrap();
+    }
+
+    #[test]
+    fn files_without_exif_should_be_reported_as_issues() {
+        let src_dir = get_test_dir("files_without_exif_should_be_reported_as_issues");
+
+        fs::write(src_dir.join("without-exif.jpg"), "not a jpeg").unwrap();
+
+        let no_exif_config = get_no_exif_config(false);
+
+        let report = diag_path(&src_dir, &get_file_filter(), &no_exif_config,
+                               &get_exif_config(), &get_diag_config(), show_progress).unwrap();
+
+        assert_eq!(report.files_with_issues, vec![src_dir.join("without-exif.jpg")]);
+        assert_eq!(report.records[0].get_issues_string(), "no-exif");
+
+        // text output doesn't need records
+        let mut diag_config = get_diag_config();
+        diag_config.keep_records = false;
+
+        let report = diag_path(&src_dir, &get_file_filter(), &no_exif_config,
+                               &get_exif_config(), &diag_config, show_progress).unwrap();
+
+        assert_eq!(report.files_with_issues.len(), 1);
+        assert_eq!(report.issue_counts.get(&IssueCategory::NoExif), Some(&1));
+        assert!(report.records.is_empty());
+
+        fs::remove_dir_all(&src_dir).unwrap();
+    }
+
+    /// Empty directory for test files, should be removed at the end of test.
+    fn get_test_dir(test_name: &str) -> PathBuf {
+        let dir_path = env::temp_dir().join("imgtag-tests").join(test_name);
+
+        if dir_path.exists() {
+            fs::remove_dir_all(&dir_path).unwrap();
+        }
+
+        fs::create_dir_all(&dir_path).unwrap();
+        dir_path
+    }
+
+    fn get_no_exif_config(extract_dates_from_path: bool) -> NoExifConfig {
+        NoExifConfig {
+            extract_dates_from_path,
+            skip_dir_names_for_date_extract: Vec::new(),
+            force_year: false,
+            year: 0,
+            fallback: None
+        }
+    }
+
+    fn get_file_filter() -> FileFilter {
+        FileFilter {
+            extensions: get_extension_filters(),
+            sniff_content: false,
+            exclude_rules: ExcludeRules::new(),
+            follow_symlinks: false
+        }
+    }
+
+    fn get_exif_config() -> ExifConfig {
+        ExifConfig {
+            date_tags: DEFAULT_DATE_TAGS.to_vec(),
+            timezone: None,
+            time_shift: TimeShiftConfig { rules: Vec::new(), default_shift: None }
+        }
+    }
+
+    fn get_diag_config() -> DiagConfig {
+        DiagConfig {
+            template: parse_template(DEFAULT_TEMPLATE).unwrap(),
+            month_names: get_month_names(DEFAULT_LOCALE).unwrap(),
+            with_milliseconds: false,
+            keep_records: true
+        }
+    }
+
+    fn show_progress(discovered_files: usize, processed_files: usize, with_issues: usize) {
+        print!("\r");
+        print!("progress: {} (discovered: {}, with issues: {})",
+               processed_files, discovered_files, with_issues);
+    }
+}
@@ -1,20 +1,441 @@
 pub mod domain {
+    use std::fmt;
+    use std::path::PathBuf;
+    use chrono::FixedOffset;
+    use crate::template::template::PathTemplate;
+    use crate::locale::locale::MonthNames;
+    use crate::time_shift::time_shift::TimeShiftConfig;
+    use crate::exclusions::exclusions::ExcludeRules;
+
     /// Behaviour config for files without EXIF or without 'Date created' exif-property.
     pub struct NoExifConfig {
         pub extract_dates_from_path: bool,
         pub skip_dir_names_for_date_extract: Vec<String>,
         pub force_year: bool,
-        pub year: i32
+        pub year: i32,
+
+        /// Filesystem timestamp for files without date in EXIF and path, checked before forced year
+        pub fallback: Option<FileTimestamp>
     }
 
     impl NoExifConfig {
         pub fn to_string(&self) -> String {
             return String::from(
                 format!(
-                    "extract_dates_from_path: {}, force_year: {}, year: {}",
-                    self.extract_dates_from_path, self.force_year, self.year
+                    "extract_dates_from_path: {}, force_year: {}, year: {}, fallback: {}",
+                    self.extract_dates_from_path, self.force_year, self.year,
+                    self.fallback.map(|fallback| fallback.to_string())
+                                 .unwrap_or(String::from("none"))
+                )
+            );
+        }
+    }
+
+    /// Filesystem timestamp, less trustworthy than EXIF: changed by copying, editing, backups.
+    #[derive(Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Debug)]
+    pub enum FileTimestamp {
+        /// Last modification
+        Modified,
+
+        /// Last status change (Unix), creation on other systems
+        Changed,
+
+        /// Creation, not supported by some filesystems
+        Birth
+    }
+
+    impl fmt::Display for FileTimestamp {
+        fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+            let name = match self {
+                FileTimestamp::Modified => "mtime",
+                FileTimestamp::Changed => "ctime",
+                FileTimestamp::Birth => "birthtime"
+            };
+
+            write!(f, "{}", name)
+        }
+    }
+
+    /// Which files are processed.
+    pub struct FileFilter {
+        /// Lower case extensions without dot. Empty - all files.
+        pub extensions: Vec<String>,
+
+        /// Accept files with other extensions if content is supported (magic bytes)
+        pub sniff_content: bool,
+
+        /// Paths excluded from traversal, in addition to `.imgtagignore` files
+        pub exclude_rules: ExcludeRules,
+
+        /// Follow symbolic links to files and directories
+        pub follow_symlinks: bool
+    }
+
+    impl FileFilter {
+        pub fn to_string(&self) -> String {
+            format!("extensions: {}, sniff_content: {}, exclude: {}, follow_symlinks: {}",
+                    self.extensions.join(","), self.sniff_content,
+                    self.exclude_rules.to_string(), self.follow_symlinks)
+        }
+    }
+
+    /// Behaviour config for `reorg` command.
+    pub struct ReorganizeConfig {
+        /// Only build the plan, don't touch the filesystem.
+        pub dry_run: bool,
+        pub placement_mode: PlacementMode,
+        pub on_conflict: ConflictPolicy,
+
+        /// Destination path template
+        pub template: PathTemplate,
+
+        pub month_names: MonthNames,
+
+        /// Include milliseconds into `{datetime}`
+        pub with_milliseconds: bool,
+
+        /// RAW files get date of JPEG (or other image) with the same base name
+        pub group_raw: bool,
+
+        /// Worker threads for date extraction and placement, 1 - sequential processing
+        pub jobs: usize,
+
+        /// Decode names which aren't valid UTF-8 for destination, `None` - keep bytes as is
+        pub name_encoding: Option<NameEncoding>,
+
+        /// Place only one copy of files with the same content
+        pub dedupe: bool,
+
+        /// Files without date are copied here, relative source path is preserved
+        pub unknown_dir: Option<PathBuf>
+    }
+
+    impl ReorganizeConfig {
+        pub fn to_string(&self) -> String {
+            return String::from(
+                format!(
+                    "dry_run: {}, placement_mode: {}, on_conflict: {}, template: '{}', \
+                     month_names: {}, with_milliseconds: {}, group_raw: {}, jobs: {}, \
+                     name_encoding: {}, dedupe: {}, unknown_dir: {}",
+                    self.dry_run, self.placement_mode, self.on_conflict,
+                    self.template.to_string(), self.month_names.to_string(),
+                    self.with_milliseconds, self.group_raw, self.jobs,
+                    self.name_encoding.map(|encoding| encoding.to_string())
+                                      .unwrap_or(String::from("keep")),
+                    self.dedupe,
+                    self.unknown_dir.as_ref().map(|unknown_dir| unknown_dir.display().to_string())
+                                    .unwrap_or(String::from("none"))
                 )
             );
         }
     }
+
+    /// File meta-data (EXIF or video) can't be read.
+    #[derive(Clone, PartialEq, Debug)]
+    pub enum MetadataError {
+        /// File can't be read
+        Unreadable(String),
+
+        /// File doesn't contain meta-data or it can't be parsed
+        NoMetadata(String)
+    }
+
+    impl fmt::Display for MetadataError {
+        fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+            match self {
+                MetadataError::Unreadable(message) => write!(f, "{}", message),
+                MetadataError::NoMetadata(message) => write!(f, "{}", message)
+            }
+        }
+    }
+
+    /// Why file date hasn't been taken from meta-data.
+    #[derive(Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Debug)]
+    pub enum DateIssue {
+        Unreadable,
+        NoMetadata,
+
+        /// Meta-data without date tags
+        NoDate,
+
+        /// Date tags with unsupported values
+        InvalidDate
+    }
+
+    /// Planned destination shown by `diag` command.
+    pub struct DiagConfig {
+        /// Destination path template
+        pub template: PathTemplate,
+
+        pub month_names: MonthNames,
+
+        /// Include milliseconds into `{datetime}`
+        pub with_milliseconds: bool,
+
+        /// Keep per-file records for JSON/CSV output
+        pub keep_records: bool
+    }
+
+    impl DiagConfig {
+        pub fn to_string(&self) -> String {
+            return String::from(
+                format!(
+                    "template: '{}', month_names: {}, with_milliseconds: {}, keep_records: {}",
+                    self.template.to_string(), self.month_names.to_string(),
+                    self.with_milliseconds, self.keep_records
+                )
+            );
+        }
+    }
+
+    /// How file is placed into destination path.
+    #[derive(Clone, Copy, PartialEq, Debug)]
+    pub enum PlacementMode {
+        Copy,
+
+        /// Rename file if possible, otherwise copy, verify and remove source file.
+        Move,
+
+        HardLink,
+
+        /// Symbolic link to absolute path of source file
+        SymLink,
+
+        /// Copy-on-write clone, fallback to copy if filesystem doesn't support it
+        Reflink
+    }
+
+    impl fmt::Display for PlacementMode {
+        fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+            let name = match self {
+                PlacementMode::Copy => "copy",
+                PlacementMode::Move => "move",
+                PlacementMode::HardLink => "hard-link",
+                PlacementMode::SymLink => "sym-link",
+                PlacementMode::Reflink => "reflink"
+            };
+
+            write!(f, "{}", name)
+        }
+    }
+
+    /// Legacy encoding of file names, for example from FAT-formatted camera cards.
+    #[derive(Clone, Copy, PartialEq, Debug)]
+    pub enum NameEncoding {
+        Cp1251,
+
+        /// DOS code page, used for FAT short names
+        Cp866
+    }
+
+    impl fmt::Display for NameEncoding {
+        fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+            let name = match self {
+                NameEncoding::Cp1251 => "cp1251",
+                NameEncoding::Cp866 => "cp866"
+            };
+
+            write!(f, "{}", name)
+        }
+    }
+
+    /// EXIF related settings.
+    pub struct ExifConfig {
+        /// Tags with date, in priority order
+        pub date_tags: Vec<ExifDateTag>,
+
+        /// Convert EXIF time to time zone (if offset is known from EXIF)
+        pub timezone: Option<TargetTimeZone>,
+
+        /// Camera clock correction, applied before time zone conversion
+        pub time_shift: TimeShiftConfig
+    }
+
+    impl ExifConfig {
+        pub fn to_string(&self) -> String {
+            let date_tags: Vec<String> = self.date_tags.iter()
+                                                       .map(|date_tag| date_tag.to_string())
+                                                       .collect();
+
+            let timezone = match &self.timezone {
+                Some(timezone) => timezone.to_string(),
+                None => String::from("none")
+            };
+
+            return String::from(
+                format!("date_tags: {}, timezone: {}, time_shift: {}",
+                        date_tags.join(","), timezone, self.time_shift.to_string())
+            );
+        }
+    }
+
+    /// Time zone for EXIF time normalization.
+    #[derive(Clone, Copy, PartialEq, Debug)]
+    pub enum TargetTimeZone {
+        Utc,
+
+        /// Time zone of operating system
+        Local,
+
+        Fixed(FixedOffset)
+    }
+
+    impl fmt::Display for TargetTimeZone {
+        fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+            match self {
+                TargetTimeZone::Utc => write!(f, "utc"),
+                TargetTimeZone::Local => write!(f, "local"),
+                TargetTimeZone::Fixed(offset) => write!(f, "{}", offset)
+            }
+        }
+    }
+
+    /// EXIF tags which might contain date of picture.
+    #[derive(Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Debug)]
+    pub enum ExifDateTag {
+        DateTimeOriginal,
+        DateTimeDigitized,
+
+        /// Modification date
+        DateTime
+    }
+
+    impl ExifDateTag {
+        pub fn from_name(name: &str) -> Option<ExifDateTag> {
+            match name.trim().to_lowercase().as_str() {
+                "datetimeoriginal" => Some(ExifDateTag::DateTimeOriginal),
+                "datetimedigitized" => Some(ExifDateTag::DateTimeDigitized),
+                "datetime" | "modifydate" => Some(ExifDateTag::DateTime),
+                _ => None
+            }
+        }
+    }
+
+    impl fmt::Display for ExifDateTag {
+        fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+            let name = match self {
+                ExifDateTag::DateTimeOriginal => "DateTimeOriginal",
+                ExifDateTag::DateTimeDigitized => "DateTimeDigitized",
+                ExifDateTag::DateTime => "DateTime"
+            };
+
+            write!(f, "{}", name)
+        }
+    }
+
+    /// Video container properties which might contain date of video.
+    #[derive(Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Debug)]
+    pub enum VideoDateTag {
+        /// `com.apple.quicktime.creationdate`, local time with offset
+        QuickTimeCreationDate,
+
+        /// `mvhd` box, UTC
+        MovieHeader,
+
+        /// `tkhd` box, UTC
+        TrackHeader
+    }
+
+    impl fmt::Display for VideoDateTag {
+        fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+            let name = match self {
+                VideoDateTag::QuickTimeCreationDate => "creationdate",
+                VideoDateTag::MovieHeader => "mvhd",
+                VideoDateTag::TrackHeader => "tkhd"
+            };
+
+            write!(f, "{}", name)
+        }
+    }
+
+    /// Date patterns recognized in file paths.
+    #[derive(Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Debug)]
+    pub enum PathDatePattern {
+        /// 20191123
+        Solid,
+
+        /// 2019-11-23
+        WithHyphens,
+
+        /// 2019.11.23
+        WithDots
+    }
+
+    impl fmt::Display for PathDatePattern {
+        fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+            let name = match self {
+                PathDatePattern::Solid => "YYYYMMDD",
+                PathDatePattern::WithHyphens => "YYYY-MM-DD",
+                PathDatePattern::WithDots => "YYYY.MM.DD"
+            };
+
+            write!(f, "{}", name)
+        }
+    }
+
+    /// Where the date for a file has been taken from.
+    #[derive(Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Debug)]
+    pub enum DateSource {
+        Exif(ExifDateTag),
+        Video(VideoDateTag),
+        Path(PathDatePattern),
+        ForcedYear,
+        FileTime(FileTimestamp)
+    }
+
+    impl DateSource {
+        /// Short name without details, safe for file names.
+        pub fn name(&self) -> &str {
+            match self {
+                DateSource::Exif(_) => "exif",
+                DateSource::Video(_) => "video",
+                DateSource::Path(_) => "path",
+                DateSource::ForcedYear => "forced-year",
+                DateSource::FileTime(_) => "fs"
+            }
+        }
+    }
+
+    impl fmt::Display for DateSource {
+        fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+            match self {
+                DateSource::Exif(date_tag) => write!(f, "{}:{}", self.name(), date_tag),
+                DateSource::Video(date_tag) => write!(f, "{}:{}", self.name(), date_tag),
+                DateSource::Path(pattern) => write!(f, "{}:{}", self.name(), pattern),
+                DateSource::FileTime(timestamp) => write!(f, "{}:{}", self.name(), timestamp),
+                _ => write!(f, "{}", self.name())
+            }
+        }
+    }
+
+    /// What to do if destination file already exists. Files with identical content
+    /// are always skipped.
+    #[derive(Clone, Copy, PartialEq, Debug)]
+    pub enum ConflictPolicy {
+        Skip,
+        Overwrite,
+
+        /// Append numeric suffix to file name
+        Rename,
+
+        /// Stop processing
+        Fail,
+
+        /// Keep file with larger size
+        KeepLarger
+    }
+
+    impl fmt::Display for ConflictPolicy {
+        fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+            let name = match self {
+                ConflictPolicy::Skip => "skip",
+                ConflictPolicy::Overwrite => "overwrite",
+                ConflictPolicy::Rename => "rename",
+                ConflictPolicy::Fail => "fail",
+                ConflictPolicy::KeepLarger => "keep-larger"
+            };
+
+            write!(f, "{}", name)
+        }
+    }
 }
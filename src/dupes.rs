@@ -0,0 +1,183 @@
+pub mod dupes {
+    use std::{io, fs};
+    use std::path::PathBuf;
+    use std::collections::{HashMap, HashSet};
+    use serde_json::{json, Value};
+    use crate::files::files::{walk_files, get_content_hash, get_file_id, FileId};
+    use crate::domain::domain::FileFilter;
+
+    /// Byte-identical files.
+    pub struct DuplicateGroup {
+        /// BLAKE3 hash of content, hex
+        pub content_hash: String,
+
+        /// Size of each file in bytes
+        pub file_size: u64,
+
+        pub files: Vec<PathBuf>
+    }
+
+    impl DuplicateGroup {
+        /// Space freed if only one file of group is kept
+        pub fn reclaimable_size(&self) -> u64 {
+            self.file_size * (self.files.len() as u64 - 1)
+        }
+    }
+
+    pub struct DupesReport {
+        pub files_total: usize,
+
+        /// Sorted by reclaimable space, largest first
+        pub groups: Vec<DuplicateGroup>,
+
+        /// Files and directories skipped by exclusion rules
+        pub excluded_paths: usize,
+
+        /// Directories and entries which couldn't be read, traversal has been continued
+        pub traversal_errors: Vec<String>,
+
+        /// Symbolic links with missing target
+        pub broken_links: Vec<PathBuf>
+    }
+
+    impl DupesReport {
+        pub fn reclaimable_size(&self) -> u64 {
+            self.groups.iter().map(|group| group.reclaimable_size()).sum()
+        }
+
+        pub fn to_json(&self) -> Value {
+            json!({
+                "files_total": self.files_total,
+                "groups": self.groups.iter().map(|group| json!({
+                    "hash": group.content_hash,
+                    "size": group.file_size,
+                    "reclaimable_size": group.reclaimable_size(),
+                    "files": group.files.iter()
+                                        .map(|file_path| file_path.to_string_lossy())
+                                        .collect::<Vec<_>>()
+                })).collect::<Vec<Value>>(),
+                "reclaimable_size": self.reclaimable_size(),
+                "excluded_paths": self.excluded_paths,
+                "traversal_errors": self.traversal_errors,
+                "broken_links": self.broken_links.iter()
+                                                 .map(|file_path| file_path.to_string_lossy())
+                                                 .collect::<Vec<_>>()
+            })
+        }
+    }
+
+    /// Find byte-identical files in source paths. Only files with the same size are hashed.
+    pub fn find_duplicates(src_paths: &Vec<PathBuf>, file_filter: &FileFilter,
+                           on_progress: fn(discovered: usize, processed: usize))
+                                                        -> Result<DupesReport, io::Error> {
+        info!("find duplicates in paths: {:?}", src_paths);
+        info!("file filter: {}", file_filter.to_string());
+
+        let mut report = DupesReport {
+            files_total: 0,
+            groups: Vec::new(),
+            excluded_paths: 0,
+            traversal_errors: Vec::new(),
+            broken_links: Vec::new()
+        };
+
+        // file size > files, source paths might overlap
+        let mut files_by_size: HashMap<u64, Vec<PathBuf>> = HashMap::new();
+
+        // hard links and links to found files aren't duplicates, they don't take space
+        let mut known_files: HashSet<FileId> = HashSet::new();
+
+        for src_path in src_paths {
+            let mut walker = match walk_files(src_path, file_filter) {
+                Ok(walker) => walker,
+                Err(e) => {
+                    error!("unable to get files from path '{}': {}", src_path.display(), e);
+                    return Err(e)
+                }
+            };
+
+            for item in walker.by_ref() {
+                let file_path = match item {
+                    Ok(file_path) => file_path,
+                    Err(e) => {
+                        error!("traversal error: {}", e);
+                        report.traversal_errors.push(e);
+                        continue
+                    }
+                };
+
+                let file_id = match get_file_id(&file_path) {
+                    Ok(file_id) => file_id,
+                    Err(e) => {
+                        error!("unable to get metadata of '{}': {}", file_path.display(), e);
+                        report.traversal_errors.push(format!("{}: {}", file_path.display(), e));
+                        continue
+                    }
+                };
+
+                if !known_files.insert(file_id) {
+                    debug!("file '{}' has been already found", file_path.display());
+                    continue
+                }
+
+                match fs::metadata(&file_path) {
+                    Ok(metadata) => {
+                        report.files_total += 1;
+                        files_by_size.entry(metadata.len()).or_default().push(file_path);
+                        on_progress(report.files_total, 0);
+                    }
+                    Err(e) => {
+                        error!("unable to get size of '{}': {}", file_path.display(), e);
+                        report.traversal_errors.push(format!("{}: {}", file_path.display(), e));
+                    }
+                }
+            }
+
+            report.excluded_paths += walker.excluded_paths;
+            report.broken_links.append(&mut walker.broken_links);
+        }
+
+        let mut processed_files: usize = 0;
+
+        for (file_size, files) in files_by_size.into_iter() {
+            if files.len() < 2 {
+                processed_files += files.len();
+                continue
+            }
+
+            // content hash > files
+            let mut files_by_hash: HashMap<String, Vec<PathBuf>> = HashMap::new();
+
+            for file_path in files {
+                match get_content_hash(&file_path) {
+                    Ok(content_hash) => {
+                        files_by_hash.entry(content_hash.to_hex().to_string())
+                                     .or_default().push(file_path);
+                    }
+                    Err(e) => {
+                        error!("unable to read file '{}': {}", file_path.display(), e);
+                        report.traversal_errors.push(format!("{}: {}", file_path.display(), e));
+                    }
+                }
+
+                processed_files += 1;
+                on_progress(report.files_total, processed_files);
+            }
+
+            for (content_hash, mut files) in files_by_hash.into_iter() {
+                if files.len() > 1 {
+                    files.sort();
+                    info!("duplicates ({} bytes): {:?}", file_size, files);
+                    report.groups.push(DuplicateGroup { content_hash, file_size, files });
+                }
+            }
+        }
+
+        report.groups.sort_by(|first, second|
+            second.reclaimable_size().cmp(&first.reclaimable_size())
+                  .then_with(|| first.files.cmp(&second.files))
+        );
+
+        Ok(report)
+    }
+}
@@ -0,0 +1,110 @@
+#[cfg(test)]
+pub mod dupes_tests {
+    use std::{env, fs};
+    use std::path::PathBuf;
+    use crate::dupes::dupes::find_duplicates;
+    use crate::domain::domain::FileFilter;
+    use crate::exclusions::exclusions::ExcludeRules;
+    use crate::get_extension_filters;
+
+    const SOURCE_DIR_NAME: &str = "img-src";
+
+    #[test]
+    fn identical_files_should_be_grouped_across_paths() {
+        let test_dir = get_test_dir("identical_files_should_be_grouped_across_paths");
+        let first_dir = test_dir.join("first");
+        let second_dir = test_dir.join("second");
+
+        let sample_file_path = format!("{}/20201010/IMG_20201010_120947.jpg", SOURCE_DIR_NAME);
+        let sample_contents = fs::read(&sample_file_path).unwrap();
+
+        fs::create_dir_all(first_dir.join("backup")).unwrap();
+        fs::create_dir_all(&second_dir).unwrap();
+
+        fs::write(first_dir.join("IMG_0001.jpg"), &sample_contents).unwrap();
+        fs::write(first_dir.join("backup/IMG_0001.jpg"), &sample_contents).unwrap();
+        fs::write(second_dir.join("copy.jpg"), &sample_contents).unwrap();
+
+        // same size, other content
+        let mut other_contents = sample_contents.clone();
+        let last_index = other_contents.len() - 1;
+        other_contents[last_index] ^= 0xff;
+        fs::write(second_dir.join("other.jpg"), other_contents).unwrap();
+
+        let src_paths = vec![first_dir.clone(), second_dir.clone(), first_dir.clone()];
+
+        let report = find_duplicates(&src_paths, &get_file_filter(), show_progress).unwrap();
+
+        assert_eq!(report.files_total, 4);
+        assert_eq!(report.groups.len(), 1);
+
+        let group = &report.groups[0];
+        assert_eq!(group.files, vec![
+            first_dir.join("IMG_0001.jpg"),
+            first_dir.join("backup/IMG_0001.jpg"),
+            second_dir.join("copy.jpg")
+        ]);
+        assert_eq!(group.file_size, sample_contents.len() as u64);
+        assert_eq!(report.reclaimable_size(), 2 * sample_contents.len() as u64);
+
+        let json = report.to_json();
+        assert_eq!(json["groups"][0]["files"].as_array().unwrap().len(), 3);
+        assert_eq!(json["reclaimable_size"], 2 * sample_contents.len() as u64);
+
+        fs::remove_dir_all(&test_dir).unwrap();
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn hard_links_should_not_be_reported_as_duplicates() {
+        let test_dir = get_test_dir("hard_links_should_not_be_reported_as_duplicates");
+
+        let sample_file_path = format!("{}/20201010/IMG_20201010_120947.jpg", SOURCE_DIR_NAME);
+        let sample_contents = fs::read(&sample_file_path).unwrap();
+
+        fs::write(test_dir.join("IMG_0001.jpg"), &sample_contents).unwrap();
+        fs::hard_link(test_dir.join("IMG_0001.jpg"), test_dir.join("link.jpg")).unwrap();
+
+        let report = find_duplicates(&vec![test_dir.clone()], &get_file_filter(), show_progress).unwrap();
+
+        assert_eq!(report.files_total, 1);
+        assert!(report.groups.is_empty());
+        assert_eq!(report.reclaimable_size(), 0);
+
+        fs::write(test_dir.join("copy.jpg"), &sample_contents).unwrap();
+
+        let report = find_duplicates(&vec![test_dir.clone()], &get_file_filter(), show_progress).unwrap();
+
+        assert_eq!(report.files_total, 2);
+        assert_eq!(report.groups[0].files.len(), 2);
+        assert_eq!(report.reclaimable_size(), sample_contents.len() as u64);
+
+        fs::remove_dir_all(&test_dir).unwrap();
+    }
+
+    fn get_file_filter() -> FileFilter {
+        FileFilter {
+            extensions: get_extension_filters(),
+            sniff_content: false,
+            exclude_rules: ExcludeRules::new(),
+            follow_symlinks: false
+        }
+    }
+
+    /// Empty directory for test files, should be removed at the end of test.
+    fn get_test_dir(test_name: &str) -> PathBuf {
+        let dir_path = env::temp_dir().join("imgtag-tests").join(test_name);
+
+        if dir_path.exists() {
+            fs::remove_dir_all(&dir_path).unwrap();
+        }
+
+        fs::create_dir_all(&dir_path).unwrap();
+        dir_path
+    }
+
+    fn show_progress(discovered_files: usize, processed_files: usize) {
+        print!("\r");
+        print!("progress: {} (discovered: {})", processed_files, discovered_files);
+    }
+}
@@ -0,0 +1,68 @@
+pub mod encoding {
+    use std::ffi::{OsStr, OsString};
+    use crate::domain::domain::NameEncoding;
+
+    pub const NAME_ENCODING_CP1251: &str = "cp1251";
+    pub const NAME_ENCODING_CP866: &str = "cp866";
+
+    pub const SUPPORTED_NAME_ENCODINGS: [&str; 2] = [NAME_ENCODING_CP1251, NAME_ENCODING_CP866];
+
+    /// Windows-1251, bytes `0x80..=0xFF`. `0x98` isn't defined.
+    const CP1251_CHARS: [char; 128] = [
+        'Ђ', 'Ѓ', '‚', 'ѓ', '„', '…', '†', '‡', '€', '‰', 'Љ', '‹', 'Њ', 'Ќ', 'Ћ', 'Џ',
+        'ђ', '‘', '’', '“', '”', '•', '–', '—', '\u{FFFD}', '™', 'љ', '›', 'њ', 'ќ', 'ћ', 'џ',
+        '\u{A0}', 'Ў', 'ў', 'Ј', '¤', 'Ґ', '¦', '§', 'Ё', '©', 'Є', '«', '¬', '\u{AD}', '®', 'Ї',
+        '°', '±', 'І', 'і', 'ґ', 'µ', '¶', '·', 'ё', '№', 'є', '»', 'ј', 'Ѕ', 'ѕ', 'ї',
+        'А', 'Б', 'В', 'Г', 'Д', 'Е', 'Ж', 'З', 'И', 'Й', 'К', 'Л', 'М', 'Н', 'О', 'П',
+        'Р', 'С', 'Т', 'У', 'Ф', 'Х', 'Ц', 'Ч', 'Ш', 'Щ', 'Ъ', 'Ы', 'Ь', 'Э', 'Ю', 'Я',
+        'а', 'б', 'в', 'г', 'д', 'е', 'ж', 'з', 'и', 'й', 'к', 'л', 'м', 'н', 'о', 'п',
+        'р', 'с', 'т', 'у', 'ф', 'х', 'ц', 'ч', 'ш', 'щ', 'ъ', 'ы', 'ь', 'э', 'ю', 'я'
+    ];
+
+    /// DOS code page 866 (FAT short names), bytes `0x80..=0xFF`.
+    const CP866_CHARS: [char; 128] = [
+        'А', 'Б', 'В', 'Г', 'Д', 'Е', 'Ж', 'З', 'И', 'Й', 'К', 'Л', 'М', 'Н', 'О', 'П',
+        'Р', 'С', 'Т', 'У', 'Ф', 'Х', 'Ц', 'Ч', 'Ш', 'Щ', 'Ъ', 'Ы', 'Ь', 'Э', 'Ю', 'Я',
+        'а', 'б', 'в', 'г', 'д', 'е', 'ж', 'з', 'и', 'й', 'к', 'л', 'м', 'н', 'о', 'п',
+        '░', '▒', '▓', '│', '┤', '╡', '╢', '╖', '╕', '╣', '║', '╗', '╝', '╜', '╛', '┐',
+        '└', '┴', '┬', '├', '─', '┼', '╞', '╟', '╚', '╔', '╩', '╦', '╠', '═', '╬', '╧',
+        '╨', '╤', '╥', '╙', '╘', '╒', '╓', '╫', '╪', '┘', '┌', '█', '▄', '▌', '▐', '▀',
+        'р', 'с', 'т', 'у', 'ф', 'х', 'ц', 'ч', 'ш', 'щ', 'ъ', 'ы', 'ь', 'э', 'ю', 'я',
+        'Ё', 'ё', 'Є', 'є', 'Ї', 'ї', 'Ў', 'ў', '°', '∙', '·', '√', '№', '¤', '■', '\u{A0}'
+    ];
+
+    pub fn get_name_encoding(name: &str) -> Option<NameEncoding> {
+        match name.to_lowercase().as_str() {
+            NAME_ENCODING_CP1251 => Some(NameEncoding::Cp1251),
+            NAME_ENCODING_CP866 => Some(NameEncoding::Cp866),
+            _ => None
+        }
+    }
+
+    /// Decode file name in legacy encoding to UTF-8. Valid UTF-8 names are returned as is.
+    #[cfg(unix)]
+    pub fn decode_file_name(file_name: &OsStr, encoding: NameEncoding) -> OsString {
+        use std::os::unix::ffi::OsStrExt;
+
+        if file_name.to_str().is_some() {
+            return file_name.to_os_string()
+        }
+
+        let chars = match encoding {
+            NameEncoding::Cp1251 => &CP1251_CHARS,
+            NameEncoding::Cp866 => &CP866_CHARS
+        };
+
+        let result: String = file_name.as_bytes().iter()
+            .map(|byte| if *byte < 0x80 { *byte as char } else { chars[(*byte - 0x80) as usize] })
+            .collect();
+
+        OsString::from(result)
+    }
+
+    /// File names are always Unicode outside of Unix.
+    #[cfg(not(unix))]
+    pub fn decode_file_name(file_name: &OsStr, _encoding: NameEncoding) -> OsString {
+        file_name.to_os_string()
+    }
+}
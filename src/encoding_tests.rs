@@ -0,0 +1,37 @@
+#[cfg(test)]
+pub mod encoding_tests {
+    use std::ffi::OsStr;
+    use crate::encoding::encoding::{get_name_encoding, decode_file_name};
+    use crate::domain::domain::NameEncoding;
+
+    #[test]
+    fn name_encoding_should_be_case_insensitive() {
+        assert_eq!(get_name_encoding("CP1251"), Some(NameEncoding::Cp1251));
+        assert_eq!(get_name_encoding("cp866"), Some(NameEncoding::Cp866));
+        assert_eq!(get_name_encoding("koi8-r"), None);
+    }
+
+    #[test]
+    fn valid_utf8_name_should_be_kept() {
+        let file_name = OsStr::new("Привет.jpg");
+        assert_eq!(decode_file_name(file_name, NameEncoding::Cp1251), file_name);
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn cp1251_name_should_be_decoded() {
+        use std::os::unix::ffi::OsStrExt;
+
+        let file_name = OsStr::from_bytes(b"\xcf\xf0\xe8\xe2\xe5\xf2.jpg");
+        assert_eq!(decode_file_name(file_name, NameEncoding::Cp1251), "Привет.jpg");
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn cp866_name_should_be_decoded() {
+        use std::os::unix::ffi::OsStrExt;
+
+        let file_name = OsStr::from_bytes(b"\x8f\xe0\xa8\xa2\xa5\xe2.jpg");
+        assert_eq!(decode_file_name(file_name, NameEncoding::Cp866), "Привет.jpg");
+    }
+}
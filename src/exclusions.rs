@@ -0,0 +1,230 @@
+pub mod exclusions {
+    use std::fs;
+    use std::path::Path;
+    use regex::Regex;
+
+    /// Ignore file with gitignore syntax, honored in every traversed directory
+    pub const IGNORE_FILE_NAME: &str = ".imgtagignore";
+
+    /// Pattern prefix for regular expressions in `--exclude`
+    const REGEX_PREFIX: &str = "re:";
+
+    const COMMENT_PREFIX: char = '#';
+    const NEGATION_PREFIX: char = '!';
+
+    /// Single exclusion rule. Glob patterns follow gitignore semantics.
+    #[derive(Clone)]
+    pub struct ExcludeRule {
+        pub pattern: String,
+
+        /// Matched against path relative to `base_path`
+        regex: Regex,
+
+        /// Directory (relative to source path) where rule is defined, empty for root
+        base_path: String,
+
+        /// `!pattern` - include previously excluded path
+        negated: bool,
+
+        /// `pattern/` - match directories only
+        dir_only: bool
+    }
+
+    /// Exclusion rules, the last matched rule wins.
+    #[derive(Clone)]
+    pub struct ExcludeRules {
+        pub rules: Vec<ExcludeRule>
+    }
+
+    impl ExcludeRules {
+        pub fn new() -> ExcludeRules {
+            ExcludeRules { rules: Vec::new() }
+        }
+
+        pub fn to_string(&self) -> String {
+            let patterns: Vec<&str> = self.rules.iter().map(|rule| rule.pattern.as_str()).collect();
+            format!("[{}]", patterns.join(", "))
+        }
+
+        /// `relative_path` - path relative to source path, separated with `/`.
+        pub fn is_excluded(&self, relative_path: &str, is_dir: bool) -> bool {
+            let mut result = false;
+
+            for rule in self.rules.iter() {
+                if rule.dir_only && !is_dir {
+                    continue
+                }
+
+                let rule_path = if rule.base_path.is_empty() {
+                    relative_path
+
+                } else if relative_path.starts_with(&format!("{}/", rule.base_path)) {
+                    &relative_path[rule.base_path.len() + 1..]
+
+                } else {
+                    continue
+                };
+
+                if rule.regex.is_match(rule_path) {
+                    result = !rule.negated;
+                }
+            }
+
+            result
+        }
+
+        pub fn extend(&mut self, rules: ExcludeRules) {
+            self.rules.extend(rules.rules);
+        }
+    }
+
+    /// Parse `--exclude` patterns: glob (gitignore semantics) or regex with `re:` prefix.
+    /// Regex is matched against path relative to source path.
+    pub fn parse_exclude_patterns(patterns: &Vec<String>) -> Result<ExcludeRules, String> {
+        let mut results = ExcludeRules::new();
+
+        for pattern in patterns.iter() {
+            let pattern = pattern.trim();
+
+            if pattern.is_empty() {
+                continue
+            }
+
+            if let Some(regex_str) = pattern.strip_prefix(REGEX_PREFIX) {
+
+                let regex = Regex::new(regex_str)
+                    .map_err(|e| format!("invalid regex '{}': {}", regex_str, e))?;
+
+                results.rules.push(ExcludeRule {
+                    pattern: String::from(pattern), regex, base_path: String::new(),
+                    negated: false, dir_only: false
+                });
+
+            } else {
+                results.rules.push(parse_glob_rule(pattern, "")?);
+            }
+        }
+
+        Ok(results)
+    }
+
+    /// Load ignore file, patterns are relative to `base_path` (directory of ignore file).
+    pub fn load_ignore_file(file_path: &Path, base_path: &str) -> Result<ExcludeRules, String> {
+        match fs::read_to_string(file_path) {
+            Ok(content) => parse_ignore_file(&content, base_path),
+            Err(e) => Err(format!("unable to read file '{}': {}", file_path.display(), e))
+        }
+    }
+
+    pub fn parse_ignore_file(content: &str, base_path: &str) -> Result<ExcludeRules, String> {
+        let mut results = ExcludeRules::new();
+
+        for (index, line) in content.lines().enumerate() {
+            let line = line.trim_end();
+
+            if line.trim().is_empty() || line.starts_with(COMMENT_PREFIX) {
+                continue
+            }
+
+            let rule = parse_glob_rule(line, base_path)
+                .map_err(|e| format!("line {}: {}", index + 1, e))?;
+
+            results.rules.push(rule);
+        }
+
+        Ok(results)
+    }
+
+    fn parse_glob_rule(pattern: &str, base_path: &str) -> Result<ExcludeRule, String> {
+        let mut glob = pattern;
+
+        let negated = glob.starts_with(NEGATION_PREFIX);
+        if negated {
+            glob = &glob[1..];
+        }
+
+        let dir_only = glob.ends_with('/');
+        if dir_only {
+            glob = glob.trim_end_matches('/');
+        }
+
+        // pattern with separator is relative to base path, otherwise matches name at any level
+        let anchored = glob.contains('/');
+        let glob = glob.trim_start_matches('/');
+
+        if glob.is_empty() {
+            return Err(format!("empty pattern '{}'", pattern))
+        }
+
+        let regex_str = format!("^{}{}$", if anchored { "" } else { "(?:.*/)?" }, glob_to_regex(glob));
+
+        let regex = Regex::new(&regex_str)
+            .map_err(|e| format!("invalid pattern '{}': {}", pattern, e))?;
+
+        Ok(
+            ExcludeRule {
+                pattern: String::from(pattern), regex,
+                base_path: String::from(base_path.trim_matches('/')),
+                negated, dir_only
+            }
+        )
+    }
+
+    fn glob_to_regex(glob: &str) -> String {
+        let mut result = String::new();
+        let chars: Vec<char> = glob.chars().collect();
+        let mut index = 0;
+
+        while index < chars.len() {
+            let c = chars[index];
+
+            match c {
+                '*' => {
+                    if chars.get(index + 1) == Some(&'*') {
+                        let at_segment_start = index == 0 || chars[index - 1] == '/';
+
+                        if at_segment_start && chars.get(index + 2) == Some(&'/') {
+                            // `**/` - zero or more directories
+                            result.push_str("(?:.*/)?");
+                            index += 3;
+                            continue
+
+                        } else {
+                            result.push_str(".*");
+                            index += 2;
+                            continue
+                        }
+                    }
+
+                    result.push_str("[^/]*");
+                }
+                '?' => result.push_str("[^/]"),
+                '[' => {
+                    match chars[index..].iter().position(|c| *c == ']') {
+                        Some(length) if length > 1 => {
+                            let class: String = chars[index + 1..index + length].iter().collect();
+                            let class = match class.strip_prefix('!') {
+                                Some(negated_class) => format!("^{}", negated_class),
+                                None => class
+                            };
+
+                            result.push_str(&format!("[{}]", class.replace('\\', "\\\\")));
+                            index += length + 1;
+                            continue
+                        }
+                        _ => result.push_str("\\[")
+                    }
+                }
+                '\\' if index + 1 < chars.len() => {
+                    result.push_str(&regex::escape(&chars[index + 1].to_string()));
+                    index += 1;
+                }
+                _ => result.push_str(&regex::escape(&c.to_string()))
+            }
+
+            index += 1;
+        }
+
+        result
+    }
+}
@@ -0,0 +1,61 @@
+#[cfg(test)]
+pub mod exclusions_tests {
+    use crate::exclusions::exclusions::{parse_exclude_patterns, parse_ignore_file};
+
+    #[test]
+    fn name_pattern_should_match_at_any_level() {
+        let rules = parse_exclude_patterns(&vec![String::from(".thumbnails")]).unwrap();
+
+        assert!(rules.is_excluded(".thumbnails", true));
+        assert!(rules.is_excluded("2020/phone/.thumbnails", true));
+        assert!(!rules.is_excluded("2020/phone/thumbnails", true));
+    }
+
+    #[test]
+    fn glob_wildcards_should_be_supported() {
+        let rules = parse_exclude_patterns(
+            &vec![String::from(".Trash-*"), String::from("*.tmp.jp?g"), String::from("backup/**/raw")]
+        ).unwrap();
+
+        assert!(rules.is_excluded("disk/.Trash-1000", true));
+        assert!(rules.is_excluded("IMG_0001.tmp.jpeg", false));
+        assert!(!rules.is_excluded("IMG_0001.jpeg", false));
+        assert!(rules.is_excluded("backup/raw", true));
+        assert!(rules.is_excluded("backup/2020/10/raw", true));
+        assert!(!rules.is_excluded("photos/backup/raw", true));
+    }
+
+    #[test]
+    fn dir_only_pattern_should_not_match_files() {
+        let rules = parse_exclude_patterns(&vec![String::from("@eaDir/")]).unwrap();
+
+        assert!(rules.is_excluded("photos/@eaDir", true));
+        assert!(!rules.is_excluded("photos/@eaDir", false));
+    }
+
+    #[test]
+    fn regex_pattern_should_be_supported() {
+        let rules = parse_exclude_patterns(&vec![String::from("re:\\.picasaoriginals$")]).unwrap();
+
+        assert!(rules.is_excluded("2019/.picasaoriginals", true));
+        assert!(!rules.is_excluded("2019/.picasaoriginals/IMG_0001.jpg", false));
+
+        assert!(parse_exclude_patterns(&vec![String::from("re:(unclosed")]).is_err());
+    }
+
+    #[test]
+    fn ignore_file_should_follow_gitignore_semantics() {
+        let rules = parse_ignore_file(
+            "# thumbnails\n\n*.thumb.jpg\n!keep.thumb.jpg\n/cache\n", "phone"
+        ).unwrap();
+
+        assert!(rules.is_excluded("phone/IMG_0001.thumb.jpg", false));
+        assert!(rules.is_excluded("phone/2020/IMG_0001.thumb.jpg", false));
+        assert!(!rules.is_excluded("phone/keep.thumb.jpg", false));
+        assert!(rules.is_excluded("phone/cache", true));
+        assert!(!rules.is_excluded("phone/2020/cache", true));
+
+        // rules are relative to directory of ignore file
+        assert!(!rules.is_excluded("camera/IMG_0001.thumb.jpg", false));
+    }
+}
@@ -1,38 +1,244 @@
 pub mod exif {
-    use chrono::NaiveDateTime;
-    use rexif::{ExifError, ExifTag};
+    use chrono::{NaiveDateTime, FixedOffset, Duration};
+    use rexif::{ExifTag, ExifEntry, TagValue};
+    use std::collections::HashMap;
+    use std::fs;
+    use std::path::Path;
+    use crate::domain::domain::{ExifDateTag, MetadataError};
+    use crate::containers::containers::{detect_container, find_exif_data, fix_raw_tiff_header};
 
     const DATETIME_FORMAT: &str = "%Y:%m:%d %H:%M:%S";
 
-    pub fn get_date_created_from_file_exif(file_path: &str) ->
-                                                        Result<Option<NaiveDateTime>, ExifError> {
-        info!("get exif 'date created' property from '{}'", file_path);
+    /// Default priority of tags with date of picture
+    pub const DEFAULT_DATE_TAGS: [ExifDateTag; 1] = [ExifDateTag::DateTimeOriginal];
 
-        let mut result: Option<NaiveDateTime> = None;
+    // Tags unknown to rexif
+    const OFFSET_TIME_TAG: u16 = 0x9010;
+    const OFFSET_TIME_ORIGINAL_TAG: u16 = 0x9011;
+    const OFFSET_TIME_DIGITIZED_TAG: u16 = 0x9012;
+    const SUB_SEC_TIME_TAG: u16 = 0x9290;
+    const SUB_SEC_TIME_ORIGINAL_TAG: u16 = 0x9291;
+    const SUB_SEC_TIME_DIGITIZED_TAG: u16 = 0x9292;
 
-        match rexif::parse_file(&file_path) {
+    const NANOS_DIGITS: usize = 9;
+
+    /// Date of picture from EXIF.
+    pub struct ExifDate {
+        /// Local time of camera, with sub-seconds if known
+        pub datetime: NaiveDateTime,
+
+        pub tag: ExifDateTag,
+
+        /// Offset from UTC of local time of camera, if known
+        pub utc_offset: Option<FixedOffset>
+    }
+
+    /// EXIF properties used for file reorganization.
+    pub struct ExifInfo {
+        pub date_created: Option<ExifDate>,
+        pub camera_model: Option<String>,
+
+        /// Date tags with unsupported values, checked before the tag with date
+        pub invalid_dates: Vec<String>
+    }
+
+    /// Date related values of single EXIF date tag.
+    #[derive(Default)]
+    struct DateValues {
+        datetime: Option<String>,
+        sub_seconds: Option<String>,
+        offset: Option<String>
+    }
+
+    /// Read EXIF properties. Date is taken from first tag (in `date_tags` order)
+    /// with valid value.
+    ///
+    /// Supported formats: JPEG, TIFF, HEIF/HEIC, PNG, WebP and camera RAW.
+    pub fn get_exif_info_from_file(file_path: &Path,
+                                   date_tags: &Vec<ExifDateTag>) -> Result<ExifInfo, MetadataError> {
+        info!("get exif properties from '{}'", file_path.display());
+
+        let mut contents = match fs::read(file_path) {
+            Ok(contents) => contents,
+            Err(e) => {
+                error!("unable to read file '{}': {}", file_path.display(), e);
+                return Err(MetadataError::Unreadable(e.to_string()))
+            }
+        };
+
+        fix_raw_tiff_header(&mut contents);
+
+        let exif_data = match detect_container(&contents) {
+            Some(container) => match find_exif_data(&contents, container) {
+                Some(exif_data) => {
+                    debug!("exif data has been found in {} container", container);
+                    exif_data
+                }
+                None => {
+                    error!("{} file '{}' doesn't contain exif data", container, file_path.display());
+                    return Err(MetadataError::NoMetadata(format!("{} without EXIF data", container)))
+                }
+            },
+            None => &contents
+        };
+
+        let mut result = ExifInfo { date_created: None, camera_model: None, invalid_dates: Vec::new() };
+
+        match rexif::parse_buffer(exif_data) {
             Ok(exif) => {
+                let mut date_values: HashMap<ExifDateTag, DateValues> = HashMap::new();
+
                 for entry in &exif.entries {
-                    if entry.tag == ExifTag::DateTimeOriginal {
-                        debug!("created date: {}", &entry.value_more_readable);
+                    collect_date_values(entry, &mut date_values);
 
-                        match NaiveDateTime::parse_from_str(
-                            &entry.value_more_readable, DATETIME_FORMAT
-                        ) {
-                            Ok(file_datetime) =>
-                                result = Some(file_datetime.to_owned()),
-                            Err(e) => error!("unsupported date format: '{}'", e)
-                        }
+                    if entry.tag == ExifTag::Model && result.camera_model.is_none() {
+                        debug!("camera model: {}", &entry.value_more_readable);
+                        result.camera_model = Some(entry.value_more_readable.to_string());
+                    }
+                }
 
+                for date_tag in date_tags.iter() {
+                    if let Some(exif_date) = get_exif_date(*date_tag, &date_values) {
+                        info!("date has been taken from '{}' tag", date_tag);
+                        result.date_created = Some(exif_date);
                         break;
                     }
+
+                    if let Some(datetime) = date_values.get(date_tag)
+                                                       .and_then(|values| values.datetime.as_ref()) {
+                        result.invalid_dates.push(format!("{}: '{}'", date_tag, datetime));
+                    }
                 }
+
                 Ok(result)
             },
             Err(e) => {
-                error!("unable to extract exif properties from '{}': {}", file_path, e);
-                Err(e)
+                error!("unable to extract exif properties from '{}': {}", file_path.display(), e);
+                Err(MetadataError::NoMetadata(e.to_string()))
+            }
+        }
+    }
+
+    /// Parse offset from UTC in EXIF format: `+03:00`, `-05:30`.
+    pub fn parse_utc_offset(value: &str) -> Option<FixedOffset> {
+        let value = value.trim();
+
+        let sign = match value.chars().next()? {
+            '+' => 1,
+            '-' => -1,
+            _ => return None
+        };
+
+        let mut parts = value[1..].split(':');
+
+        let hours: i32 = parts.next()?.parse().ok()?;
+        let minutes: i32 = parts.next().unwrap_or("0").parse().ok()?;
+
+        if parts.next().is_some() || hours > 23 || minutes > 59 {
+            return None
+        }
+
+        FixedOffset::east_opt(sign * (hours * 3600 + minutes * 60))
+    }
+
+    fn collect_date_values(entry: &ExifEntry, date_values: &mut HashMap<ExifDateTag, DateValues>) {
+        match entry.tag {
+            ExifTag::DateTimeOriginal | ExifTag::DateTimeDigitized | ExifTag::DateTime => {
+                let date_tag = match entry.tag {
+                    ExifTag::DateTimeOriginal => ExifDateTag::DateTimeOriginal,
+                    ExifTag::DateTimeDigitized => ExifDateTag::DateTimeDigitized,
+                    _ => ExifDateTag::DateTime
+                };
+
+                debug!("{}: {}", date_tag, &entry.value_more_readable);
+
+                let values = date_values.entry(date_tag).or_insert(DateValues::default());
+
+                if values.datetime.is_none() {
+                    values.datetime = Some(entry.value_more_readable.to_string());
+                }
+            }
+            ExifTag::UnknownToMe => {
+                let (date_tag, is_offset) = match entry.ifd.tag {
+                    SUB_SEC_TIME_ORIGINAL_TAG => (ExifDateTag::DateTimeOriginal, false),
+                    SUB_SEC_TIME_DIGITIZED_TAG => (ExifDateTag::DateTimeDigitized, false),
+                    SUB_SEC_TIME_TAG => (ExifDateTag::DateTime, false),
+                    OFFSET_TIME_ORIGINAL_TAG => (ExifDateTag::DateTimeOriginal, true),
+                    OFFSET_TIME_DIGITIZED_TAG => (ExifDateTag::DateTimeDigitized, true),
+                    OFFSET_TIME_TAG => (ExifDateTag::DateTime, true),
+                    _ => return
+                };
+
+                if let Some(value) = get_ascii_value(entry) {
+                    debug!("{} {}: {}", date_tag, if is_offset { "offset" } else { "sub-seconds" },
+                           value);
+
+                    let values = date_values.entry(date_tag).or_insert(DateValues::default());
+
+                    if is_offset {
+                        values.offset = Some(value);
+
+                    } else {
+                        values.sub_seconds = Some(value);
+                    }
+                }
+            }
+            _ => {}
+        }
+    }
+
+    fn get_exif_date(date_tag: ExifDateTag,
+                     date_values: &HashMap<ExifDateTag, DateValues>) -> Option<ExifDate> {
+        let values = date_values.get(&date_tag)?;
+        let datetime_str = values.datetime.as_ref()?;
+
+        match NaiveDateTime::parse_from_str(datetime_str, DATETIME_FORMAT) {
+            Ok(file_datetime) => {
+                let datetime = match values.sub_seconds.as_ref()
+                                                  .and_then(|value| parse_sub_seconds(value)) {
+                    Some(nanos) => file_datetime + Duration::nanoseconds(nanos),
+                    None => file_datetime
+                };
+
+                let utc_offset = values.offset.as_ref().and_then(|value| {
+                    let offset = parse_utc_offset(value);
+
+                    if offset.is_none() {
+                        warn!("unsupported offset format in '{}' tag: '{}'", date_tag, value);
+                    }
+
+                    offset
+                });
+
+                Some(ExifDate { datetime, tag: date_tag, utc_offset })
+            }
+            Err(e) => {
+                error!("unsupported date format in '{}' tag: '{}'", date_tag, e);
+                None
             }
         }
     }
+
+    /// Sub-seconds are stored as decimal fraction digits: "12" - 0.12 sec.
+    /// Returns nanoseconds.
+    fn parse_sub_seconds(value: &str) -> Option<i64> {
+        let digits: String = value.trim().chars().take(NANOS_DIGITS).collect();
+
+        if digits.is_empty() || !digits.chars().all(|c| c.is_ascii_digit()) {
+            return None
+        }
+
+        format!("{:0<width$}", digits, width = NANOS_DIGITS).parse().ok()
+    }
+
+    fn get_ascii_value(entry: &ExifEntry) -> Option<String> {
+        let value = match &entry.value {
+            TagValue::Ascii(value) => value.clone(),
+            _ => String::from_utf8_lossy(&entry.ifd.data).to_string()
+        };
+
+        let value = value.trim_matches(char::from(0)).trim();
+
+        if value.is_empty() { None } else { Some(String::from(value)) }
+    }
 }
@@ -1,12 +1,81 @@
 #[cfg(test)]
 pub mod exif_tests {
-    use crate::exif::exif::get_date_created_from_file_exif;
+    use crate::exif::exif::{get_exif_info_from_file, parse_utc_offset, DEFAULT_DATE_TAGS};
+    use crate::domain::domain::ExifDateTag;
+    use crate::containers_tests::containers_tests::{build_heif, build_png, build_webp};
+    use std::{fs, env};
+    use std::path::Path;
 
     #[test]
     fn return_error_for_unsupported_date_format() {
-        match get_date_created_from_file_exif("img-src/wrong-exif/wrong-exif.jpg") {
-            Ok(value) => assert!(value.is_none()),
+        match get_exif_info_from_file(Path::new("img-src/wrong-exif/wrong-exif.jpg"),
+                                      &vec![ExifDateTag::DateTimeOriginal]) {
+            Ok(exif_info) => {
+                assert!(exif_info.date_created.is_none());
+                assert!(!exif_info.invalid_dates.is_empty());
+            }
             Err(_) => panic!("result expected")
         }
     }
+
+    #[test]
+    fn date_should_be_taken_from_tags_in_priority_order() {
+        let file_path = Path::new("img-src/20201010/IMG_20201010_120947.jpg");
+
+        let exif_info = get_exif_info_from_file(file_path, &DEFAULT_DATE_TAGS.to_vec()).unwrap();
+        assert_eq!(exif_info.date_created.unwrap().tag, ExifDateTag::DateTimeOriginal);
+
+        let date_tags = vec![ExifDateTag::DateTime, ExifDateTag::DateTimeOriginal];
+        let exif_info = get_exif_info_from_file(file_path, &date_tags).unwrap();
+        assert_eq!(exif_info.date_created.unwrap().tag, ExifDateTag::DateTime);
+    }
+
+    #[test]
+    fn sub_seconds_and_offset_should_be_read() {
+        let exif_info = get_exif_info_from_file(Path::new("img-src/20201010/IMG_20201010_120947.jpg"),
+                                                &DEFAULT_DATE_TAGS.to_vec()).unwrap();
+
+        let exif_date = exif_info.date_created.unwrap();
+
+        assert_eq!(exif_date.datetime.format("%Y-%m-%d %H:%M:%S%.3f").to_string(),
+                   "2020-10-10 12:09:47.678");
+        assert_eq!(exif_date.utc_offset.unwrap().local_minus_utc(), 3 * 3600);
+    }
+
+    #[test]
+    fn utc_offset_should_be_parsed() {
+        assert_eq!(parse_utc_offset("+03:00").unwrap().local_minus_utc(), 3 * 3600);
+        assert_eq!(parse_utc_offset("-05:30").unwrap().local_minus_utc(), -(5 * 3600 + 30 * 60));
+        assert!(parse_utc_offset("03:00").is_none());
+        assert!(parse_utc_offset("+25:00").is_none());
+        assert!(parse_utc_offset("   :  ").is_none());
+    }
+
+    #[test]
+    fn date_should_be_read_from_heif_png_and_webp() {
+        let jpeg_contents = fs::read("img-src/20201010/IMG_20201010_120947.jpg").unwrap();
+
+        // APP1 segment: marker, size, "Exif\0\0", TIFF
+        let app1_size = u16::from_be_bytes([jpeg_contents[4], jpeg_contents[5]]) as usize;
+        let tiff_data = &jpeg_contents[12..4 + app1_size];
+
+        let containers = vec![
+            ("heic", build_heif(tiff_data)), ("png", build_png(tiff_data)),
+            ("webp", build_webp(tiff_data))
+        ];
+
+        for (ext, contents) in containers {
+            let file_path = env::temp_dir().join(format!("imgtag-exif-test.{}", ext));
+            fs::write(&file_path, contents).unwrap();
+
+            let exif_info = get_exif_info_from_file(&file_path,
+                                                    &DEFAULT_DATE_TAGS.to_vec()).unwrap();
+
+            assert_eq!(exif_info.date_created.unwrap().datetime.format("%Y-%m-%d %H:%M:%S").to_string(),
+                       "2020-10-10 12:09:47");
+            assert_eq!(exif_info.camera_model.unwrap(), "Pixel 2 XL");
+
+            fs::remove_file(&file_path).unwrap();
+        }
+    }
 }
@@ -1,57 +1,561 @@
 pub mod files {
     use std::{io, fs};
-    use std::path::Path;
-    use std::fs::DirEntry;
+    use std::path::{Path, PathBuf};
+    use std::fs::{DirEntry, File, OpenOptions};
+    use std::io::{BufReader, Read, Error, ErrorKind};
+    use std::collections::{HashMap, HashSet};
+    use std::rc::Rc;
+    use blake3::{Hash, Hasher};
+    use crate::containers::containers::{is_raw_file, sniff_content_type};
+    use crate::domain::domain::FileFilter;
+    use crate::exclusions::exclusions::{ExcludeRules, IGNORE_FILE_NAME, load_ignore_file};
 
-    pub fn get_files_from_path(path: &str,
-                               filter_extensions: &Vec<String>) -> Result<Vec<String>, io::Error> {
+    const COMPARE_BUFFER_SIZE: usize = 64 * 1024;
 
-        let mut results: Vec<String> = Vec::new();
+    /// Enough for magic bytes and `ftyp` box
+    const FILE_HEADER_SIZE: usize = 256;
 
-        let dir_path = Path::new(path);
+    /// Iterates over files of source path recursively (depth-first), files are yielded as
+    /// discovered. Exclusion rules from `--exclude` and `.imgtagignore` files are applied to
+    /// paths relative to source path.
+    ///
+    /// Symbolic links are skipped unless `follow_symlinks` is set. Followed directories are
+    /// entered once, so link loops and shared albums don't produce duplicates.
+    ///
+    /// Errors of nested directories are yielded as `Err`, traversal continues.
+    pub struct FileWalker<'a> {
+        file_filter: &'a FileFilter,
 
-        for entry in fs::read_dir(dir_path)? {
-            let entry = entry?;
-            debug!("file: {:?}", entry.path().file_name());
+        /// Opened directories, the last one is being read
+        dirs: Vec<DirState>,
 
-            let file_type = entry.file_type()?;
+        /// Files yielded so far
+        pub discovered_files: usize,
 
-            if file_accepted(&entry, &filter_extensions) &&
-                file_type.is_file() || file_type.is_symlink() {
-                let file_name = entry.file_name().into_string().unwrap();
+        /// Files and directories skipped by exclusion rules
+        pub excluded_paths: usize,
 
-                debug!("filename: {}", file_name);
+        /// Symbolic links with missing target, found if links are followed
+        pub broken_links: Vec<PathBuf>,
 
-                results.push(String::from(entry.path().to_str().unwrap()));
+        /// Directories entered so far, filled if links are followed
+        visited_dirs: HashSet<FileId>
+    }
+
+    /// File or directory identity: links and hard links to the same file have the same id
+    #[cfg(unix)]
+    pub type FileId = (u64, u64);
+
+    #[cfg(not(unix))]
+    pub type FileId = PathBuf;
+
+    struct DirState {
+        read_dir: fs::ReadDir,
+        path: PathBuf,
+
+        /// Relative to source path, empty for source path
+        relative_path: String,
+
+        /// Rules from `.imgtagignore` files of directory and its parents
+        ignore_rules: Rc<ExcludeRules>
+    }
+
+    /// Start traversal. Returns error if source path can't be read.
+    pub fn walk_files<'a>(path: &Path,
+                          file_filter: &'a FileFilter) -> Result<FileWalker<'a>, io::Error> {
+        let root_dir = open_dir(path, "", &Rc::new(ExcludeRules::new()))?;
+
+        let mut visited_dirs: HashSet<FileId> = HashSet::new();
+
+        if file_filter.follow_symlinks {
+            visited_dirs.insert(get_file_id(path)?);
+        }
+
+        Ok(
+            FileWalker {
+                file_filter, dirs: vec![root_dir], discovered_files: 0, excluded_paths: 0,
+                broken_links: Vec::new(), visited_dirs
             }
+        )
+    }
+
+    impl<'a> Iterator for FileWalker<'a> {
+        type Item = Result<PathBuf, String>;
+
+        fn next(&mut self) -> Option<Self::Item> {
+            loop {
+                let dir = self.dirs.last_mut()?;
+
+                let entry = match dir.read_dir.next() {
+                    Some(Ok(entry)) => entry,
+                    Some(Err(e)) => {
+                        error!("unable to read directory '{}': {}", dir.path.display(), e);
+                        return Some(Err(format!("'{}': {}", dir.path.display(), e)))
+                    }
+                    None => {
+                        self.dirs.pop();
+                        continue
+                    }
+                };
+
+                debug!("file: {:?}", entry.path().file_name());
+
+                let file_type = match entry.file_type() {
+                    Ok(file_type) => file_type,
+                    Err(e) => {
+                        error!("unable to get file type of '{}': {}", entry.path().display(), e);
+                        return Some(Err(format!("'{}': {}", entry.path().display(), e)))
+                    }
+                };
+
+                let (is_dir, is_file) = if file_type.is_symlink() {
+                    if !self.file_filter.follow_symlinks {
+                        info!("symbolic link '{}' has been skipped", entry.path().display());
+                        continue
+                    }
+
+                    match fs::metadata(entry.path()) {
+                        Ok(metadata) => (metadata.is_dir(), metadata.is_file()),
+                        Err(e) => {
+                            warn!("broken symbolic link '{}': {}", entry.path().display(), e);
+                            self.broken_links.push(entry.path());
+                            continue
+                        }
+                    }
 
-            if file_type.is_dir() {
-                if let Ok(files) = get_files_from_path(entry.path().to_str().unwrap(), filter_extensions) {
-                    files.iter().for_each(|file_path| results.push(String::from(file_path)));
+                } else {
+                    (file_type.is_dir(), file_type.is_file())
+                };
+
+                let entry_name = entry.file_name().to_string_lossy().to_string();
+
+                let relative_path = if dir.relative_path.is_empty() {
+                    entry_name
+                } else {
+                    format!("{}/{}", dir.relative_path, entry_name)
+                };
+
+                if is_excluded(&relative_path, is_dir, self.file_filter, &dir.ignore_rules) {
+                    info!("path '{}' has been excluded", relative_path);
+                    self.excluded_paths += 1;
+                    continue
+                }
+
+                if is_dir {
+                    let ignore_rules = Rc::clone(&dir.ignore_rules);
+
+                    if self.file_filter.follow_symlinks {
+                        match get_file_id(&entry.path()) {
+                            Ok(dir_id) => {
+                                if !self.visited_dirs.insert(dir_id) {
+                                    info!("directory '{}' has been already visited, skipped",
+                                          entry.path().display());
+                                    continue
+                                }
+                            }
+                            Err(e) => {
+                                error!("unable to read directory '{}': {}", entry.path().display(), e);
+                                return Some(Err(format!("'{}': {}", entry.path().display(), e)))
+                            }
+                        }
+                    }
+
+                    match open_dir(&entry.path(), &relative_path, &ignore_rules) {
+                        Ok(dir_state) => self.dirs.push(dir_state),
+                        Err(e) => {
+                            error!("unable to read directory '{}': {}", entry.path().display(), e);
+                            return Some(Err(format!("'{}': {}", entry.path().display(), e)))
+                        }
+                    }
+
+                    continue
+                }
+
+                if is_file && file_accepted(&entry, self.file_filter) {
+                    debug!("filename: {}", entry.path().display());
+                    self.discovered_files += 1;
+                    return Some(Ok(entry.path()))
                 }
             }
         }
+    }
 
-        Ok(results)
+    fn open_dir(dir_path: &Path, relative_path: &str,
+                parent_ignore_rules: &Rc<ExcludeRules>) -> Result<DirState, io::Error> {
+        let ignore_rules = get_ignore_rules(dir_path, relative_path, parent_ignore_rules)?;
+
+        Ok(
+            DirState {
+                read_dir: fs::read_dir(dir_path)?,
+                path: dir_path.to_path_buf(),
+                relative_path: String::from(relative_path),
+                ignore_rules
+            }
+        )
     }
 
-    fn file_accepted(dir_entry: &DirEntry, filter_extensions: &Vec<String>) -> bool {
-        let mut result = false;
+    /// Parent rules extended with rules from `.imgtagignore` file of directory.
+    fn get_ignore_rules(dir_path: &Path, relative_path: &str,
+                        parent_ignore_rules: &Rc<ExcludeRules>) -> Result<Rc<ExcludeRules>, io::Error> {
+        let ignore_file_path = dir_path.join(IGNORE_FILE_NAME);
+
+        if ignore_file_path.is_file() {
+            match load_ignore_file(&ignore_file_path, relative_path) {
+                Ok(rules) => {
+                    info!("ignore rules have been loaded from '{}': {}",
+                          ignore_file_path.display(), rules.to_string());
 
-        if filter_extensions.is_empty() {
-            result = true;
+                    let mut ignore_rules = ExcludeRules::clone(parent_ignore_rules);
+                    ignore_rules.extend(rules);
+                    Ok(Rc::new(ignore_rules))
+                }
+                Err(e) => {
+                    error!("invalid ignore file '{}': {}", ignore_file_path.display(), e);
+                    Err(Error::new(ErrorKind::InvalidData, e))
+                }
+            }
 
         } else {
-            match dir_entry.path().extension() {
-                Some(file_extension) => {
-                    let file_ext_str = file_extension.to_str().unwrap().to_lowercase();
+            Ok(Rc::clone(parent_ignore_rules))
+        }
+    }
 
-                    result = filter_extensions.contains(&file_ext_str)
+    #[cfg(unix)]
+    pub fn get_file_id(path: &Path) -> Result<FileId, io::Error> {
+        use std::os::unix::fs::MetadataExt;
+
+        let metadata = fs::metadata(path)?;
+        Ok((metadata.dev(), metadata.ino()))
+    }
+
+    #[cfg(not(unix))]
+    pub fn get_file_id(path: &Path) -> Result<FileId, io::Error> {
+        fs::canonicalize(path)
+    }
+
+    fn is_excluded(relative_path: &str, is_dir: bool, file_filter: &FileFilter,
+                   ignore_rules: &ExcludeRules) -> bool {
+        ignore_rules.is_excluded(relative_path, is_dir) ||
+            file_filter.exclude_rules.is_excluded(relative_path, is_dir)
+    }
+
+    fn file_accepted(dir_entry: &DirEntry, file_filter: &FileFilter) -> bool {
+        let mut result = has_accepted_extension(dir_entry, file_filter);
+
+        if !result && file_filter.sniff_content && dir_entry.path().is_file() {
+            if let Ok(header) = read_file_header(&dir_entry.path()) {
+                result = sniff_content_type(&header).is_some();
+
+                if result {
+                    info!("file '{}' has been accepted by content", dir_entry.path().display());
                 }
-                None => {}
             }
         }
 
         result
     }
+
+    fn has_accepted_extension(dir_entry: &DirEntry, file_filter: &FileFilter) -> bool {
+        if file_filter.extensions.is_empty() {
+            return true
+        }
+
+        match dir_entry.path().extension() {
+            Some(file_extension) => {
+                let file_ext_str = file_extension.to_string_lossy().to_lowercase();
+                file_filter.extensions.contains(&file_ext_str)
+            }
+            None => false
+        }
+    }
+
+    /// Read first bytes of file for format detection.
+    pub fn read_file_header(file_path: &Path) -> Result<Vec<u8>, io::Error> {
+        let file = File::open(file_path)?;
+
+        let mut results: Vec<u8> = Vec::with_capacity(FILE_HEADER_SIZE);
+        file.take(FILE_HEADER_SIZE as u64).read_to_end(&mut results)?;
+
+        Ok(results)
+    }
+
+    /// Move file. Rename is used when possible (same filesystem), otherwise file is copied,
+    /// verified byte-for-byte and only then source file is removed.
+    /// Existing destination file isn't replaced, collisions are resolved by caller.
+    pub fn move_file(src_file_path: &Path, dest_file_path: &Path) -> Result<(), io::Error> {
+        if fs::symlink_metadata(dest_file_path).is_ok() {
+            return Err(Error::new(ErrorKind::AlreadyExists,
+                                  format!("destination file '{}' already exists",
+                                          dest_file_path.display())))
+        }
+
+        match fs::rename(src_file_path, dest_file_path) {
+            Ok(_) => {
+                debug!("file has been renamed");
+                Ok(())
+            }
+            Err(e) if e.kind() == ErrorKind::CrossesDevices => {
+                info!("unable to rename file ({}), fallback to copy-verify-delete", e);
+
+                copy_new_file(src_file_path, dest_file_path)?;
+
+                if files_are_equal(src_file_path, dest_file_path)? {
+                    fs::remove_file(src_file_path)
+
+                } else {
+                    error!("copied file '{}' doesn't match source '{}'",
+                           dest_file_path.display(), src_file_path.display());
+
+                    fs::remove_file(dest_file_path)?;
+
+                    Err(Error::new(ErrorKind::InvalidData,
+                                   "copied file doesn't match source file"))
+                }
+            }
+            Err(e) => Err(e)
+        }
+    }
+
+    /// Copy file contents and permissions, fails if destination file already exists.
+    fn copy_new_file(src_file_path: &Path, dest_file_path: &Path) -> Result<(), io::Error> {
+        let mut src_file = File::open(src_file_path)?;
+        let mut dest_file = OpenOptions::new().write(true).create_new(true).open(dest_file_path)?;
+
+        io::copy(&mut src_file, &mut dest_file)?;
+        dest_file.set_permissions(src_file.metadata()?.permissions())?;
+
+        Ok(())
+    }
+
+    /// Create symbolic link to absolute path of source file.
+    pub fn create_symlink(src_file_path: &Path, dest_file_path: &Path) -> Result<(), io::Error> {
+        let src_absolute_path = fs::canonicalize(src_file_path)?;
+
+        #[cfg(unix)]
+        return std::os::unix::fs::symlink(&src_absolute_path, dest_file_path);
+
+        #[cfg(windows)]
+        return std::os::windows::fs::symlink_file(&src_absolute_path, dest_file_path);
+    }
+
+    /// Create copy-on-write clone of file. Falls back to regular copy if filesystem
+    /// doesn't support reflinks.
+    pub fn reflink_file(src_file_path: &Path, dest_file_path: &Path) -> Result<(), io::Error> {
+        match reflink_copy::reflink_or_copy(src_file_path, dest_file_path)? {
+            None => debug!("reflink has been created"),
+            Some(_) => info!("reflinks aren't supported, file has been copied")
+        }
+
+        Ok(())
+    }
+
+    /// Compare files content byte-for-byte.
+    pub fn files_are_equal(first_file_path: &Path,
+                           second_file_path: &Path) -> Result<bool, io::Error> {
+        let first_file = File::open(first_file_path)?;
+        let second_file = File::open(second_file_path)?;
+
+        if first_file.metadata()?.len() != second_file.metadata()?.len() {
+            return Ok(false)
+        }
+
+        let mut first_reader = BufReader::new(first_file);
+        let mut second_reader = BufReader::new(second_file);
+
+        let mut first_buffer = vec![0u8; COMPARE_BUFFER_SIZE];
+        let mut second_buffer = vec![0u8; COMPARE_BUFFER_SIZE];
+
+        loop {
+            let read_bytes = read_chunk(&mut first_reader, &mut first_buffer)?;
+            let second_read_bytes = read_chunk(&mut second_reader, &mut second_buffer)?;
+
+            if read_bytes != second_read_bytes ||
+                first_buffer[..read_bytes] != second_buffer[..second_read_bytes] {
+                return Ok(false)
+            }
+
+            if read_bytes == 0 {
+                return Ok(true)
+            }
+        }
+    }
+
+    /// BLAKE3 hash of file content.
+    pub fn get_content_hash(file_path: &Path) -> Result<Hash, io::Error> {
+        let mut reader = BufReader::new(File::open(file_path)?);
+        let mut buffer = vec![0u8; COMPARE_BUFFER_SIZE];
+        let mut hasher = Hasher::new();
+
+        loop {
+            let read_bytes = read_chunk(&mut reader, &mut buffer)?;
+
+            if read_bytes == 0 {
+                return Ok(hasher.finalize())
+            }
+
+            hasher.update(&buffer[..read_bytes]);
+        }
+    }
+
+    /// Find companions for RAW files: non-RAW file (JPEG, HEIC, etc.) with the same
+    /// base name in the same directory. Returns RAW file path > companion file path.
+    pub fn get_raw_companions(files: &Vec<PathBuf>) -> HashMap<PathBuf, PathBuf> {
+        let mut companions: HashMap<PathBuf, PathBuf> = HashMap::new();
+
+        for file_path in files.iter().filter(|file_path| !is_raw_file(file_path)) {
+            companions.entry(get_group_key(file_path)).or_insert(file_path.clone());
+        }
+
+        files.iter()
+             .filter(|file_path| is_raw_file(file_path))
+             .filter_map(|file_path|
+                 companions.get(&get_group_key(file_path))
+                           .map(|companion| (file_path.clone(), companion.clone()))
+             )
+             .collect()
+    }
+
+    /// RAW + companion groups, directory is indexed once, when file from it is requested
+    /// (before any of its files is moved).
+    pub struct RawGroups {
+        /// Exclusion rules are applied to paths relative to source path
+        src_path: PathBuf,
+
+        indexed_dirs: HashSet<PathBuf>,
+
+        /// Directory path > rules from `.imgtagignore` files of directory and its parents
+        dir_ignore_rules: HashMap<PathBuf, Rc<ExcludeRules>>,
+
+        /// RAW file path > companion file path
+        raw_companions: HashMap<PathBuf, PathBuf>,
+
+        companion_files: HashSet<PathBuf>
+    }
+
+    impl RawGroups {
+        pub fn new(src_path: &Path) -> RawGroups {
+            RawGroups {
+                src_path: src_path.to_path_buf(), indexed_dirs: HashSet::new(),
+                dir_ignore_rules: HashMap::new(), raw_companions: HashMap::new(),
+                companion_files: HashSet::new()
+            }
+        }
+
+        /// Path of file which date is used for the whole group: companion file for RAW file
+        /// and companion file itself. `None` if file isn't grouped.
+        pub fn get_group_file(&mut self, file_path: &Path,
+                              file_filter: &FileFilter) -> Option<PathBuf> {
+            let dir_path = file_path.parent().unwrap_or(Path::new(""));
+
+            if !self.indexed_dirs.contains(dir_path) {
+                self.index_dir(dir_path, file_filter);
+            }
+
+            match self.raw_companions.get(file_path) {
+                Some(companion_file_path) => Some(companion_file_path.clone()),
+                None if self.companion_files.contains(file_path) => Some(file_path.to_path_buf()),
+                None => None
+            }
+        }
+
+        fn index_dir(&mut self, dir_path: &Path, file_filter: &FileFilter) {
+            let mut files: Vec<PathBuf> = Vec::new();
+
+            let relative_dir_path = get_relative_path(dir_path, &self.src_path);
+            let ignore_rules = self.get_dir_ignore_rules(dir_path);
+
+            if let (Some(relative_dir_path), Some(ignore_rules), Ok(read_dir)) =
+                                            (relative_dir_path, ignore_rules, fs::read_dir(dir_path)) {
+                for entry in read_dir.filter_map(|entry| entry.ok()) {
+                    // same rules as in `FileWalker`
+                    let is_file = match entry.file_type() {
+                        Ok(file_type) if file_type.is_symlink() => file_filter.follow_symlinks &&
+                            fs::metadata(entry.path()).map(|metadata| metadata.is_file())
+                                                      .unwrap_or(false),
+                        Ok(file_type) => file_type.is_file(),
+                        Err(_) => false
+                    };
+
+                    let entry_name = entry.file_name().to_string_lossy().to_string();
+
+                    let relative_path = if relative_dir_path.is_empty() {
+                        entry_name
+                    } else {
+                        format!("{}/{}", relative_dir_path, entry_name)
+                    };
+
+                    if is_file && !is_excluded(&relative_path, false, file_filter, &ignore_rules) &&
+                        has_accepted_extension(&entry, file_filter) {
+                        files.push(entry.path());
+                    }
+                }
+            }
+
+            let raw_companions = get_raw_companions(&files);
+
+            self.companion_files.extend(raw_companions.values().cloned());
+            self.raw_companions.extend(raw_companions);
+            self.indexed_dirs.insert(dir_path.to_path_buf());
+        }
+
+        /// Rules are loaded the same way as by `FileWalker`, from source path down to directory.
+        /// `None` if directory is outside of source path or has invalid ignore file.
+        fn get_dir_ignore_rules(&mut self, dir_path: &Path) -> Option<Rc<ExcludeRules>> {
+            if let Some(ignore_rules) = self.dir_ignore_rules.get(dir_path) {
+                return Some(Rc::clone(ignore_rules))
+            }
+
+            let relative_path = get_relative_path(dir_path, &self.src_path)?;
+
+            let parent_ignore_rules = if relative_path.is_empty() {
+                Rc::new(ExcludeRules::new())
+            } else {
+                self.get_dir_ignore_rules(dir_path.parent()?)?
+            };
+
+            let ignore_rules = get_ignore_rules(dir_path, &relative_path,
+                                                &parent_ignore_rules).ok()?;
+
+            self.dir_ignore_rules.insert(dir_path.to_path_buf(), Rc::clone(&ignore_rules));
+
+            Some(ignore_rules)
+        }
+    }
+
+    /// Path relative to source path with `/` separators, as built by `FileWalker`.
+    fn get_relative_path(path: &Path, src_path: &Path) -> Option<String> {
+        let relative_path = path.strip_prefix(src_path).ok()?;
+
+        Some(
+            relative_path.components()
+                         .map(|component| component.as_os_str().to_string_lossy().to_string())
+                         .collect::<Vec<String>>()
+                         .join("/")
+        )
+    }
+
+    /// Parent directory and base name without extension, case insensitive.
+    fn get_group_key(file_path: &Path) -> PathBuf {
+        let parent = file_path.parent().unwrap_or(Path::new(""));
+        let file_stem = file_path.file_stem().unwrap_or(file_path.as_os_str());
+
+        match file_stem.to_str() {
+            Some(file_stem) => parent.join(file_stem.to_lowercase()),
+            None => parent.join(file_stem)
+        }
+    }
+
+    /// Fill buffer as much as possible, returns amount of bytes read (0 - end of file).
+    fn read_chunk<R: Read>(reader: &mut R, buffer: &mut [u8]) -> Result<usize, io::Error> {
+        let mut total_read = 0;
+
+        while total_read < buffer.len() {
+            match reader.read(&mut buffer[total_read..])? {
+                0 => break,
+                read_bytes => total_read += read_bytes
+            }
+        }
+
+        Ok(total_read)
+    }
 }
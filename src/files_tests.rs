@@ -0,0 +1,55 @@
+#[cfg(test)]
+pub mod files_tests {
+    use std::{env, fs};
+    use std::io::ErrorKind;
+    use std::path::PathBuf;
+    use crate::files::files::move_file;
+
+    #[test]
+    fn existing_destination_should_not_be_replaced_on_move() {
+        let dir_path = get_test_dir("existing_destination_should_not_be_replaced_on_move");
+
+        let src_file_path = dir_path.join("IMG_0001.jpg");
+        let dest_file_path = dir_path.join("IMG_0002.jpg");
+        fs::write(&src_file_path, "source").unwrap();
+        fs::write(&dest_file_path, "destination").unwrap();
+
+        let error = move_file(&src_file_path, &dest_file_path).unwrap_err();
+
+        assert_eq!(error.kind(), ErrorKind::AlreadyExists);
+        assert_eq!(fs::read_to_string(&src_file_path).unwrap(), "source");
+        assert_eq!(fs::read_to_string(&dest_file_path).unwrap(), "destination");
+
+        fs::remove_file(&dest_file_path).unwrap();
+        move_file(&src_file_path, &dest_file_path).unwrap();
+
+        assert!(!src_file_path.exists());
+        assert_eq!(fs::read_to_string(&dest_file_path).unwrap(), "source");
+
+        fs::remove_dir_all(&dir_path).unwrap();
+    }
+
+    #[test]
+    fn rename_error_should_be_returned_without_copy() {
+        let dir_path = get_test_dir("rename_error_should_be_returned_without_copy");
+
+        let dest_file_path = dir_path.join("missing/IMG_0001.jpg");
+        let error = move_file(&dir_path.join("IMG_0001.jpg"), &dest_file_path).unwrap_err();
+
+        assert_eq!(error.kind(), ErrorKind::NotFound);
+        assert!(!dest_file_path.exists());
+
+        fs::remove_dir_all(&dir_path).unwrap();
+    }
+
+    fn get_test_dir(test_name: &str) -> PathBuf {
+        let dir_path = env::temp_dir().join("imgtag-tests").join(test_name);
+
+        if dir_path.exists() {
+            fs::remove_dir_all(&dir_path).unwrap();
+        }
+
+        fs::create_dir_all(&dir_path).unwrap();
+        dir_path
+    }
+}
@@ -0,0 +1,126 @@
+pub mod locale {
+    pub const LOCALE_RU: &str = "ru";
+    pub const LOCALE_EN: &str = "en";
+    pub const LOCALE_DE: &str = "de";
+
+    /// Month number only: 07
+    pub const LOCALE_NUMERIC: &str = "numeric";
+
+    /// Month number with name: 07-Июль, 07-July, 07-Juli
+    pub const LOCALE_NUMERIC_RU: &str = "numeric-ru";
+    pub const LOCALE_NUMERIC_EN: &str = "numeric-en";
+    pub const LOCALE_NUMERIC_DE: &str = "numeric-de";
+
+    pub const DEFAULT_LOCALE: &str = LOCALE_RU;
+
+    pub const SUPPORTED_LOCALES: [&str; 7] = [
+        LOCALE_RU, LOCALE_EN, LOCALE_DE,
+        LOCALE_NUMERIC, LOCALE_NUMERIC_RU, LOCALE_NUMERIC_EN, LOCALE_NUMERIC_DE
+    ];
+
+    const CUSTOM_LOCALE: &str = "custom";
+
+    const MONTHS_IN_YEAR: usize = 12;
+
+    const RU_MONTH_NAMES: [&str; MONTHS_IN_YEAR] = [
+        "Январь", "Февраль", "Март", "Апрель", "Май", "Июнь",
+        "Июль", "Август", "Сентябрь", "Октябрь", "Ноябрь", "Декабрь"
+    ];
+
+    const EN_MONTH_NAMES: [&str; MONTHS_IN_YEAR] = [
+        "January", "February", "March", "April", "May", "June",
+        "July", "August", "September", "October", "November", "December"
+    ];
+
+    const DE_MONTH_NAMES: [&str; MONTHS_IN_YEAR] = [
+        "Januar", "Februar", "März", "April", "Mai", "Juni",
+        "Juli", "August", "September", "Oktober", "November", "Dezember"
+    ];
+
+    /// Month names for destination path.
+    pub struct MonthNames {
+        locale: String,
+
+        /// `None` - month number only
+        names: Option<Vec<String>>,
+
+        /// Prefix month name with month number: 07-July
+        numeric_prefix: bool
+    }
+
+    impl MonthNames {
+        /// `month_index` - 1..12
+        pub fn get_month_name(&self, month_index: u32) -> String {
+            let name = if month_index >= 1 && month_index as usize <= MONTHS_IN_YEAR {
+                self.names.as_ref().map(|names| names[month_index as usize - 1].clone())
+
+            } else { None };
+
+            match name {
+                Some(name) => {
+                    if self.numeric_prefix {
+                        format!("{:02}-{}", month_index, name)
+
+                    } else { name }
+                }
+                None => format!("{:02}", month_index)
+            }
+        }
+
+        pub fn to_string(&self) -> String {
+            self.locale.clone()
+        }
+    }
+
+    pub fn get_month_names(locale: &str) -> Result<MonthNames, String> {
+        let (names, numeric_prefix) = match locale.to_lowercase().as_str() {
+            LOCALE_RU => (Some(&RU_MONTH_NAMES), false),
+            LOCALE_EN => (Some(&EN_MONTH_NAMES), false),
+            LOCALE_DE => (Some(&DE_MONTH_NAMES), false),
+            LOCALE_NUMERIC => (None, true),
+            LOCALE_NUMERIC_RU => (Some(&RU_MONTH_NAMES), true),
+            LOCALE_NUMERIC_EN => (Some(&EN_MONTH_NAMES), true),
+            LOCALE_NUMERIC_DE => (Some(&DE_MONTH_NAMES), true),
+            _ => return Err(format!("unsupported locale '{}'", locale))
+        };
+
+        Ok(
+            MonthNames {
+                locale: locale.to_lowercase(),
+                names: names.map(|names| names.iter().map(|name| String::from(*name)).collect()),
+                numeric_prefix
+            }
+        )
+    }
+
+    /// Replace month names with custom ones. Month number prefix setting of locale is kept.
+    pub fn with_custom_month_names(month_names: MonthNames,
+                                   custom_names: &Vec<String>) -> Result<MonthNames, String> {
+        if custom_names.len() != MONTHS_IN_YEAR {
+            return Err(format!("{} month names expected, got {}",
+                               MONTHS_IN_YEAR, custom_names.len()))
+        }
+
+        if custom_names.iter().any(|name| name.trim().is_empty()) {
+            return Err(String::from("month name can't be empty"))
+        }
+
+        // month name is used as directory name
+        if let Some(name) = custom_names.iter().find(|name| !is_valid_dir_name(name.trim())) {
+            return Err(format!("month name '{}' can't be used as directory name", name))
+        }
+
+        Ok(
+            MonthNames {
+                locale: String::from(CUSTOM_LOCALE),
+                names: Some(custom_names.iter().map(|name| String::from(name.trim())).collect()),
+                numeric_prefix: month_names.numeric_prefix
+            }
+        )
+    }
+
+    fn is_valid_dir_name(name: &str) -> bool {
+        name != "." && name != ".." &&
+            !name.chars().any(|c| c == '/' || c == '\\' || c.is_control())
+    }
+}
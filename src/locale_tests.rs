@@ -0,0 +1,54 @@
+#[cfg(test)]
+pub mod locale_tests {
+    use crate::locale::locale::{get_month_names, with_custom_month_names, DEFAULT_LOCALE};
+
+    #[test]
+    fn russian_month_names_should_be_used_by_default() {
+        let month_names = get_month_names(DEFAULT_LOCALE).unwrap();
+
+        assert_eq!(month_names.get_month_name(1), "Январь");
+        assert_eq!(month_names.get_month_name(7), "Июль");
+        assert_eq!(month_names.get_month_name(12), "Декабрь");
+    }
+
+    #[test]
+    fn month_names_should_be_localized() {
+        assert_eq!(get_month_names("en").unwrap().get_month_name(7), "July");
+        assert_eq!(get_month_names("de").unwrap().get_month_name(3), "März");
+    }
+
+    #[test]
+    fn numeric_locale_should_return_month_number() {
+        assert_eq!(get_month_names("numeric").unwrap().get_month_name(7), "07");
+        assert_eq!(get_month_names("numeric-en").unwrap().get_month_name(7), "07-July");
+        assert_eq!(get_month_names("numeric-ru").unwrap().get_month_name(11), "11-Ноябрь");
+    }
+
+    #[test]
+    fn custom_month_names_should_be_used() {
+        let custom_names: Vec<String> = "Jan,Feb,Mar,Apr,May,Jun,Jul,Aug,Sep,Oct,Nov,Dec"
+            .split(",").map(|name| String::from(name)).collect();
+
+        let month_names = with_custom_month_names(
+            get_month_names("numeric-en").unwrap(), &custom_names
+        ).unwrap();
+
+        assert_eq!(month_names.get_month_name(8), "08-Aug");
+    }
+
+    #[test]
+    fn invalid_month_names_should_be_rejected() {
+        assert!(get_month_names("fr").is_err());
+
+        let custom_names: Vec<String> = vec![String::from("Jan"), String::from("Feb")];
+        assert!(with_custom_month_names(get_month_names("en").unwrap(), &custom_names).is_err());
+
+        for invalid_name in &["", " ", ".", "..", "../Feb", "Jan/Feb", "Jan\\Feb"] {
+            let mut custom_names: Vec<String> = "Jan,Feb,Mar,Apr,May,Jun,Jul,Aug,Sep,Oct,Nov,Dec"
+                .split(",").map(|name| String::from(name)).collect();
+            custom_names[1] = String::from(*invalid_name);
+
+            assert!(with_custom_month_names(get_month_names("en").unwrap(), &custom_names).is_err());
+        }
+    }
+}
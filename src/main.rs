@@ -5,10 +5,24 @@ extern crate log4rs;
 use crate::logging::logging::get_logging_config;
 use clap::{ArgMatches, Arg, App, SubCommand};
 use std::process::exit;
-use crate::commands::commands::reorganize_files;
-use crate::domain::domain::NoExifConfig;
+use crate::commands::commands::{reorganize_files, ReorganizeReport, UndatedFile};
+use crate::domain::domain::{FileFilter, NoExifConfig, ReorganizeConfig, PlacementMode, ConflictPolicy,
+                            ExifConfig, ExifDateTag, TargetTimeZone, DiagConfig, FileTimestamp};
+use crate::exif::exif::{DEFAULT_DATE_TAGS, parse_utc_offset};
+use crate::video::video::VIDEO_EXTENSIONS;
+use crate::containers::containers::{IMAGE_EXTENSIONS, RAW_EXTENSIONS};
+use crate::exclusions::exclusions::parse_exclude_patterns;
+use crate::time_shift::time_shift::{TimeShiftConfig, parse_time_shift, load_time_shift_rules,
+                                    MAX_TIME_SHIFT_HOURS};
 use chrono::Local;
 use crate::diag::diag::diag_path;
+use crate::dupes::dupes::find_duplicates;
+use crate::similar::similar::{find_similar, DEFAULT_MAX_DISTANCE};
+use crate::template::template::{parse_template, PathTemplate, DEFAULT_TEMPLATE};
+use crate::locale::locale::{get_month_names, with_custom_month_names, MonthNames,
+                            DEFAULT_LOCALE, SUPPORTED_LOCALES};
+use crate::encoding::encoding::{get_name_encoding, SUPPORTED_NAME_ENCODINGS};
+use std::path::{Path, PathBuf};
 
 mod commands;
 mod commands_tests;
@@ -19,9 +33,37 @@ mod path_parser_tests;
 mod logging;
 mod domain;
 mod diag;
+mod diag_tests;
+mod dupes;
+mod dupes_tests;
+mod similar;
+mod similar_tests;
 mod files;
+mod files_tests;
 mod exif;
 mod exif_tests;
+mod dates;
+
+mod containers;
+mod containers_tests;
+
+mod video;
+mod video_tests;
+
+mod exclusions;
+mod exclusions_tests;
+
+mod time_shift;
+mod time_shift_tests;
+
+mod template;
+mod template_tests;
+
+mod locale;
+mod locale_tests;
+
+mod encoding;
+mod encoding_tests;
 
 const REORG_COMMAND: &str = "reorg";
 
@@ -30,18 +72,111 @@ const DEST_PATH_ARG: &str = "dest-dir";
 
 const DIAG_COMMAND: &str = "diag";
 
+const DUPES_COMMAND: &str = "dupes";
+
+const SIMILAR_COMMAND: &str = "similar";
+
+/// Max Hamming distance between perceptual hashes of similar images, 0..64
+const MAX_DISTANCE_OPTION: &str = "max-distance";
+
+/// Report format
+const FORMAT_OPTION: &str = "format";
+const FORMAT_TEXT_VALUE: &str = "text";
+const FORMAT_JSON_VALUE: &str = "json";
+const FORMAT_CSV_VALUE: &str = "csv";
+
 const SKIP_DIR_NAMES_FOR_DATE_EXTRACT_ARG: &str = "skip-dir-names-for-date-extract";
 const DONT_EXTRACT_DATE_FROM_PATH_FLAG: &str = "dont-extract-date-from-path";
 
+/// EXIF tags with date, in priority order
+const EXIF_DATE_TAGS_OPTION: &str = "exif-date-tags";
+
+/// Convert EXIF time to time zone
+const TIMEZONE_OPTION: &str = "timezone";
+const TIMEZONE_UTC_VALUE: &str = "utc";
+const TIMEZONE_LOCAL_VALUE: &str = "local";
+
+/// Extension filter
+const INCLUDE_EXT_OPTION: &str = "include-ext";
+const EXCLUDE_EXT_OPTION: &str = "exclude-ext";
+const SNIFF_CONTENT_FLAG: &str = "sniff-content";
+
+/// Exclude paths from traversal: glob or regex (with 're:' prefix)
+const EXCLUDE_OPTION: &str = "exclude";
+
+/// Follow symbolic links to files and directories
+const FOLLOW_SYMLINKS_FLAG: &str = "follow-symlinks";
+
+/// Camera clock correction
+const TIME_SHIFT_OPTION: &str = "time-shift";
+const TIME_SHIFT_RULES_OPTION: &str = "time-shift-rules";
+
 /// Force year for files without EXIF or without 'Date created' exif-property
 const FORCE_YEAR_OPTION: &str = "force-year";
 const YEAR_VALUE: &str = "year";
 
+/// Filesystem timestamp for files without date in EXIF and path
+const FALLBACK_OPTION: &str = "fallback";
+const FALLBACK_MTIME_VALUE: &str = "mtime";
+const FALLBACK_CTIME_VALUE: &str = "ctime";
+const FALLBACK_BIRTHTIME_VALUE: &str = "birthtime";
+
+/// Show planned operations without modifications in filesystem
+const DRY_RUN_FLAG: &str = "dry-run";
+
+/// Move files instead of copy
+const MOVE_FLAG: &str = "move";
+
+/// Create links instead of copies
+const LINK_OPTION: &str = "link";
+const LINK_HARD_VALUE: &str = "hard";
+const LINK_SYM_VALUE: &str = "sym";
+const LINK_REFLINK_VALUE: &str = "reflink";
+
+/// What to do if destination file already exists
+const ON_CONFLICT_OPTION: &str = "on-conflict";
+const ON_CONFLICT_SKIP_VALUE: &str = "skip";
+const ON_CONFLICT_OVERWRITE_VALUE: &str = "overwrite";
+const ON_CONFLICT_RENAME_VALUE: &str = "rename";
+const ON_CONFLICT_FAIL_VALUE: &str = "fail";
+const ON_CONFLICT_KEEP_LARGER_VALUE: &str = "keep-larger";
+
+/// Destination path template
+const TEMPLATE_OPTION: &str = "template";
+
+/// Language of month names
+const LOCALE_OPTION: &str = "locale";
+
+/// Custom month names, separated with comma
+const MONTH_NAMES_OPTION: &str = "month-names";
+
+/// Include milliseconds into file names
+const WITH_MILLISECONDS_FLAG: &str = "with-milliseconds";
+
+/// Use date of JPEG for RAW file with the same name
+const GROUP_RAW_FLAG: &str = "group-raw";
+
+/// Worker threads for date extraction and file placement
+const JOBS_OPTION: &str = "jobs";
+const JOBS_DEFAULT_VALUE: &str = "1";
+
+/// Place only one copy of files with the same content
+const DEDUPE_FLAG: &str = "dedupe";
+
+/// Legacy encoding of file names which aren't valid UTF-8
+const NAME_ENCODING_OPTION: &str = "name-encoding";
+
+/// Copy files without date to this directory
+const UNKNOWN_DIR_OPTION: &str = "unknown-dir";
+
 const LOG_LEVEL_ARGUMENT: &str = "log-level";
 const LOG_LEVEL_DEFAULT_VALUE: &str = "info";
 
 const ERROR_EXIT_CODE: i32 = 1;
 
+/// Command has been completed, but some files haven't been placed
+const UNPLACED_FILES_EXIT_CODE: i32 = 2;
+
 fn main() {
     let src_path_arg = Arg::with_name(SRC_PATH_ARG)
                                 .help("source path")
@@ -63,6 +198,146 @@ fn main() {
         .takes_value(true).empty_values(false)
         .required(false);
 
+    let exif_date_tags_arg = Arg::with_name(EXIF_DATE_TAGS_OPTION)
+        .help("EXIF tags with date in priority order, separated with comma. \
+                  Supported tags: DateTimeOriginal, DateTimeDigitized, DateTime. \
+                  Default: DateTimeOriginal")
+        .long(EXIF_DATE_TAGS_OPTION)
+        .value_name("tags")
+        .takes_value(true).empty_values(false)
+        .required(false);
+
+    let timezone_arg = Arg::with_name(TIMEZONE_OPTION)
+        .help("convert EXIF time to time zone if offset is known from EXIF. \
+                  Possible values: utc, local, offset (+03:00, -05:30)")
+        .long(TIMEZONE_OPTION)
+        .value_name(TIMEZONE_OPTION)
+        .allow_hyphen_values(true)
+        .takes_value(true).empty_values(false)
+        .required(false);
+
+    let time_shift_arg = Arg::with_name(TIME_SHIFT_OPTION)
+        .help("shift EXIF time to correct camera clock. Format: [+|-]HH:MM[:SS]; \
+                  Example: +01:00:00")
+        .long(TIME_SHIFT_OPTION)
+        .value_name("shift")
+        .allow_hyphen_values(true)
+        .takes_value(true).empty_values(false)
+        .required(false);
+
+    let time_shift_rules_arg = Arg::with_name(TIME_SHIFT_RULES_OPTION)
+        .help("file with time shift rules per camera model or source directory. \
+                  Line format: 'model:<camera model> = <shift>' or 'dir:<directory name> = <shift>'")
+        .long(TIME_SHIFT_RULES_OPTION)
+        .value_name("file")
+        .takes_value(true).empty_values(false)
+        .required(false);
+
+    let include_ext_arg = Arg::with_name(INCLUDE_EXT_OPTION)
+        .help("process files with extensions in addition to supported ones. \
+                  Extensions should be separated with comma; Example: jpe,jfif")
+        .long(INCLUDE_EXT_OPTION)
+        .value_name("extensions")
+        .takes_value(true).empty_values(false)
+        .required(false);
+
+    let exclude_ext_arg = Arg::with_name(EXCLUDE_EXT_OPTION)
+        .help("skip files with extensions. Extensions should be separated with comma; \
+                  Example: tiff,png")
+        .long(EXCLUDE_EXT_OPTION)
+        .value_name("extensions")
+        .takes_value(true).empty_values(false)
+        .required(false);
+
+    let sniff_content_arg = Arg::with_name(SNIFF_CONTENT_FLAG)
+        .help("detect supported files with other extensions by content (magic bytes), \
+                  for example 'photo.dat' which is JPEG")
+        .long(SNIFF_CONTENT_FLAG)
+        .takes_value(false)
+        .required(false);
+
+    let exclude_arg = Arg::with_name(EXCLUDE_OPTION)
+        .help("exclude paths (relative to source path) from traversal. Glob with gitignore \
+                  semantics or regex with 're:' prefix; option can be used multiple times. \
+                  Example: --exclude .thumbnails --exclude '@eaDir/' --exclude 're:\\.Trash-\\d+'. \
+                  Patterns from .imgtagignore files are applied as well")
+        .long(EXCLUDE_OPTION)
+        .value_name("pattern")
+        .multiple(true).number_of_values(1)
+        .takes_value(true).empty_values(false)
+        .required(false);
+
+    let follow_symlinks_arg = Arg::with_name(FOLLOW_SYMLINKS_FLAG)
+        .help("follow symbolic links to files and directories. Each directory is visited once, \
+                  link loops are skipped. Without option links are ignored \
+                  (earlier versions processed links to files)")
+        .long(FOLLOW_SYMLINKS_FLAG)
+        .takes_value(false)
+        .required(false);
+
+    let force_year_arg = Arg::with_name(FORCE_YEAR_OPTION)
+        .help("force year for files without EXIF or without 'Date created' exif-property")
+        .long(FORCE_YEAR_OPTION)
+        .value_name(YEAR_VALUE)
+        .takes_value(true).required(false);
+
+    let fallback_arg = Arg::with_name(FALLBACK_OPTION)
+        .help("use filesystem timestamp for files without date in EXIF and path, before forced year. \
+               Less trustworthy, '__mtime' (ctime, birthtime) suffix is added to file name")
+        .long(FALLBACK_OPTION)
+        .value_name("timestamp")
+        .possible_values(&[FALLBACK_MTIME_VALUE, FALLBACK_CTIME_VALUE, FALLBACK_BIRTHTIME_VALUE])
+        .takes_value(true).required(false);
+
+    let template_arg = Arg::with_name(TEMPLATE_OPTION)
+        .help("destination path template, relative to destination path. \
+                  Placeholders: {year}, {quarter}, {month}, {month_name}, {day}, {hour}, \
+                  {minute}, {second}, {date}, {datetime}, {camera_model}, \
+                  {original_name}, {name}, {ext}, {date_source}")
+        .long(TEMPLATE_OPTION)
+        .value_name(TEMPLATE_OPTION)
+        .default_value(DEFAULT_TEMPLATE)
+        .takes_value(true).required(false);
+
+    let locale_arg = Arg::with_name(LOCALE_OPTION)
+        .help("language of month names. numeric - month number only (07), \
+                  numeric-<lang> - month number with name (07-July)")
+        .long(LOCALE_OPTION)
+        .value_name(LOCALE_OPTION)
+        .possible_values(&SUPPORTED_LOCALES)
+        .case_insensitive(true)
+        .default_value(DEFAULT_LOCALE)
+        .takes_value(true).required(false);
+
+    let month_names_arg = Arg::with_name(MONTH_NAMES_OPTION)
+        .help("custom month names (12), separated with comma. \
+                  Example: Jan,Feb,Mar,Apr,May,Jun,Jul,Aug,Sep,Oct,Nov,Dec")
+        .long(MONTH_NAMES_OPTION)
+        .value_name("names")
+        .takes_value(true).empty_values(false)
+        .required(false);
+
+    let with_milliseconds_arg = Arg::with_name(WITH_MILLISECONDS_FLAG)
+        .help("include milliseconds (EXIF sub-seconds) into {datetime}: \
+                  YYYY-MM-DD__HH-MM-SS-mmm")
+        .long(WITH_MILLISECONDS_FLAG)
+        .takes_value(false)
+        .required(false);
+
+    let src_paths_arg = Arg::with_name(SRC_PATH_ARG)
+        .help("source paths")
+        .value_name(SRC_PATH_ARG)
+        .multiple(true)
+        .takes_value(true).required(true);
+
+    let report_format_arg = Arg::with_name(FORMAT_OPTION)
+        .help("report format")
+        .long(FORMAT_OPTION)
+        .value_name(FORMAT_OPTION)
+        .possible_values(&[FORMAT_TEXT_VALUE, FORMAT_JSON_VALUE])
+        .default_value(FORMAT_TEXT_VALUE)
+        .takes_value(true).required(false);
+
     let matches = App::new("imgtag")
         .version("0.4.0")
         .about("Image files hierarchy tool")
@@ -87,11 +362,103 @@ fn main() {
             )
             .arg(&dont_extract_date_from_path_arg)
             .arg(&skip_dir_names_for_date_extract_arg)
+            .arg(&exif_date_tags_arg)
+            .arg(&timezone_arg)
+            .arg(&time_shift_arg)
+            .arg(&time_shift_rules_arg)
+            .arg(&include_ext_arg)
+            .arg(&exclude_ext_arg)
+            .arg(&sniff_content_arg)
+            .arg(&exclude_arg)
+            .arg(&follow_symlinks_arg)
+            .arg(&force_year_arg)
+            .arg(&fallback_arg)
+            .arg(
+                Arg::with_name(DRY_RUN_FLAG)
+                    .help("show planned operations (source > destination) without modifications \
+                           in filesystem")
+                    .long(DRY_RUN_FLAG)
+                    .takes_value(false)
+                    .required(false)
+            )
+            .arg(
+                Arg::with_name(MOVE_FLAG)
+                    .help("move files instead of copy. Source file is removed only after \
+                           destination file has been verified")
+                    .long(MOVE_FLAG)
+                    .takes_value(false)
+                    .required(false)
+            )
+            .arg(
+                Arg::with_name(LINK_OPTION)
+                    .help("create links instead of copies: hard - hard links, sym - symbolic links, \
+                           reflink - copy-on-write clones (fallback to copy)")
+                    .long(LINK_OPTION)
+                    .value_name("type")
+                    .possible_values(&[LINK_HARD_VALUE, LINK_SYM_VALUE, LINK_REFLINK_VALUE])
+                    .conflicts_with(MOVE_FLAG)
+                    .takes_value(true).required(false)
+            )
+            .arg(
+                Arg::with_name(ON_CONFLICT_OPTION)
+                    .help("what to do if destination file already exists. Files with identical \
+                           content are always skipped. rename - append numeric suffix (default, \
+                           earlier versions overwrote existing files), fail - stop processing \
+                           and show report")
+                    .long(ON_CONFLICT_OPTION)
+                    .value_name("policy")
+                    .possible_values(&[ON_CONFLICT_SKIP_VALUE, ON_CONFLICT_OVERWRITE_VALUE,
+                                       ON_CONFLICT_RENAME_VALUE, ON_CONFLICT_FAIL_VALUE,
+                                       ON_CONFLICT_KEEP_LARGER_VALUE])
+                    .default_value(ON_CONFLICT_RENAME_VALUE)
+                    .takes_value(true).required(false)
+            )
+            .arg(&template_arg)
+            .arg(&locale_arg)
+            .arg(&month_names_arg)
+            .arg(&with_milliseconds_arg)
+            .arg(
+                Arg::with_name(GROUP_RAW_FLAG)
+                    .help("keep RAW files together with JPEG (or other image) with the same name: \
+                           RAW file gets date of its twin")
+                    .long(GROUP_RAW_FLAG)
+                    .takes_value(false)
+                    .required(false)
+            )
+            .arg(
+                Arg::with_name(JOBS_OPTION)
+                    .help("worker threads for date extraction and file placement. \
+                           Collisions are resolved in order of source files")
+                    .long(JOBS_OPTION)
+                    .short("j")
+                    .value_name("N")
+                    .default_value(JOBS_DEFAULT_VALUE)
+                    .takes_value(true).required(false)
+            )
+            .arg(
+                Arg::with_name(DEDUPE_FLAG)
+                    .help("place only one copy of files with the same content (BLAKE3 hash), \
+                           other copies are listed in report")
+                    .long(DEDUPE_FLAG)
+                    .takes_value(false)
+                    .required(false)
+            )
+            .arg(
+                Arg::with_name(UNKNOWN_DIR_OPTION)
+                    .help("copy files without date to this directory, path relative to source path \
+                           is kept. By default such files are left in source path")
+                    .long(UNKNOWN_DIR_OPTION)
+                    .value_name(UNKNOWN_DIR_OPTION)
+                    .takes_value(true).required(false)
+            )
             .arg(
-                Arg::with_name(FORCE_YEAR_OPTION)
-                    .help("force year for files without EXIF or without 'Date created' exif-property")
-                    .long(FORCE_YEAR_OPTION)
-                    .value_name(YEAR_VALUE)
+                Arg::with_name(NAME_ENCODING_OPTION)
+                    .help("decode file names which aren't valid UTF-8 from legacy encoding \
+                           for {original_name}, {name}. By default names are kept as is")
+                    .long(NAME_ENCODING_OPTION)
+                    .value_name("encoding")
+                    .possible_values(&SUPPORTED_NAME_ENCODINGS)
+                    .case_insensitive(true)
                     .takes_value(true).required(false)
             )
         )
@@ -100,6 +467,60 @@ fn main() {
             .arg(&src_path_arg)
             .arg(dont_extract_date_from_path_arg)
             .arg(skip_dir_names_for_date_extract_arg)
+            .arg(exif_date_tags_arg)
+            .arg(timezone_arg)
+            .arg(time_shift_arg)
+            .arg(time_shift_rules_arg)
+            .arg(&include_ext_arg)
+            .arg(&exclude_ext_arg)
+            .arg(&sniff_content_arg)
+            .arg(&exclude_arg)
+            .arg(&follow_symlinks_arg)
+            .arg(force_year_arg)
+            .arg(fallback_arg)
+            .arg(template_arg)
+            .arg(locale_arg)
+            .arg(month_names_arg)
+            .arg(with_milliseconds_arg)
+            .arg(
+                Arg::with_name(FORMAT_OPTION)
+                    .help("report format. json, csv - record per file with date, date source, \
+                           meta-data error and destination relative to destination path")
+                    .long(FORMAT_OPTION)
+                    .value_name(FORMAT_OPTION)
+                    .possible_values(&[FORMAT_TEXT_VALUE, FORMAT_JSON_VALUE, FORMAT_CSV_VALUE])
+                    .default_value(FORMAT_TEXT_VALUE)
+                    .takes_value(true).required(false)
+            )
+        )
+        .subcommand(SubCommand::with_name(DUPES_COMMAND)
+                        .about("find byte-identical files without modifications in filesystem.")
+            .arg(&src_paths_arg)
+            .arg(&include_ext_arg)
+            .arg(&exclude_ext_arg)
+            .arg(&sniff_content_arg)
+            .arg(&exclude_arg)
+            .arg(&follow_symlinks_arg)
+            .arg(&report_format_arg)
+        )
+        .subcommand(SubCommand::with_name(SIMILAR_COMMAND)
+                        .about("find resized and re-encoded copies of JPEG images \
+                                without modifications in filesystem.")
+            .arg(&src_paths_arg)
+            .arg(include_ext_arg)
+            .arg(exclude_ext_arg)
+            .arg(sniff_content_arg)
+            .arg(exclude_arg)
+            .arg(follow_symlinks_arg)
+            .arg(report_format_arg)
+            .arg(
+                Arg::with_name(MAX_DISTANCE_OPTION)
+                    .help("max Hamming distance between perceptual hashes (dHash, 64 bits) \
+                           of similar images. 0 - visually identical only")
+                    .long(MAX_DISTANCE_OPTION)
+                    .value_name("bits")
+                    .takes_value(true).required(false)
+            )
         )
         .get_matches();
 
@@ -115,37 +536,101 @@ fn main() {
 
             let skip_dir_names_for_date_extract: Vec<String> = get_dir_names_skip_masks(args);
 
-            let src_path: &str = args.value_of(SRC_PATH_ARG)
-                                     .expect("invalid value for src-path argument");
+            let src_path: &Path = Path::new(args.value_of_os(SRC_PATH_ARG)
+                                                .expect("invalid value for src-path argument"));
 
-            let dest_path: &str = args.value_of(DEST_PATH_ARG)
-                                      .expect("invalid value for dest-path argument");
+            let dest_path: &Path = Path::new(args.value_of_os(DEST_PATH_ARG)
+                                                 .expect("invalid value for dest-path argument"));
 
-            let (force_year_for_unknown, year) = if matches.is_present(FORCE_YEAR_OPTION) {
-                let value_str = matches.value_of(FORCE_YEAR_OPTION).unwrap();
-                let year: i32 = value_str.parse::<i32>().unwrap() as i32;
-                (true, year)
-
-            } else {
-                (false, 0)
-            };
+            let (force_year_for_unknown, year) = get_force_year(args);
 
             let no_exif_config: NoExifConfig = NoExifConfig {
                 extract_dates_from_path,
                 skip_dir_names_for_date_extract,
                 force_year: force_year_for_unknown,
-                year
+                year,
+                fallback: get_fallback(args)
+            };
+
+            let placement_mode = get_placement_mode(args);
+
+            let template = get_template(args);
+
+            let reorganize_config = ReorganizeConfig {
+                dry_run: args.is_present(DRY_RUN_FLAG),
+                placement_mode,
+                on_conflict: get_conflict_policy(args),
+                template,
+                month_names: get_month_names_from_args(args),
+                with_milliseconds: args.is_present(WITH_MILLISECONDS_FLAG),
+                group_raw: args.is_present(GROUP_RAW_FLAG),
+                jobs: get_jobs(args),
+                name_encoding: args.value_of(NAME_ENCODING_OPTION).and_then(get_name_encoding),
+                dedupe: args.is_present(DEDUPE_FLAG),
+                unknown_dir: args.value_of_os(UNKNOWN_DIR_OPTION).map(PathBuf::from)
             };
 
             print_operation_start();
 
-            let ext_filters: Vec<String> = get_extension_filters();
+            let file_filter = get_file_filter(args);
+
+            let exif_config = get_exif_config(args);
+
+            match reorganize_files(src_path, dest_path, &file_filter,
+                                   &no_exif_config, &exif_config, &reorganize_config,
+                                   show_reorganize_progress) {
+                Ok(report) => {
+                    if reorganize_config.dry_run {
+                        println!("\n---\nPlanned operations (dry-run):");
+                        report.planned_files.iter().for_each(|planned_file|
+                            println!("{} -> {} ({})", planned_file.src_file_path.display(),
+                                     planned_file.dest_file_path.display(),
+                                     planned_file.date_source)
+                        );
+
+                        print_collisions(&report);
+                        print_duplicates(&report);
+                        print_left_in_place(&report);
+                        print_undated_files(&report);
+
+                        println!("---\nFiles total: {} (planned: {})",
+                                 report.files_total, report.planned_files.len());
+                        print_excluded_paths(report.excluded_paths);
+                        print_traversal_errors(&report.traversal_errors);
+                        print_broken_links(&report.broken_links);
+                        print_operation_finish();
+
+                    } else {
+                        print_collisions(&report);
+                        print_duplicates(&report);
+                        print_left_in_place(&report);
+                        print_undated_files(&report);
+                        print_failed_files(&report);
+
+                        print_operation_finish();
+
+                        if report.get_unplaced_files() == 0 && !report.stopped {
+                            println!("\n---\nAll files have been reorganized");
+                        }
+
+                        print_excluded_paths(report.excluded_paths);
+                        print_traversal_errors(&report.traversal_errors);
+                        print_broken_links(&report.broken_links);
+                    }
+
+                    println!("---\nPlaced: {}, skipped: {}, failed: {}", report.placed_files,
+                             report.get_skipped_files(), report.failed_files.len());
+
+                    if report.stopped {
+                        eprintln!("processing has been stopped: destination file already exists \
+                                   (--on-conflict={})", ON_CONFLICT_FAIL_VALUE);
+                        exit(ERROR_EXIT_CODE)
+                    }
+
+                    if report.get_unplaced_files() > 0 {
+                        exit(UNPLACED_FILES_EXIT_CODE)
+                    }
 
-            match reorganize_files(src_path, dest_path, &ext_filters,
-                                   &no_exif_config, show_reorganize_progress) {
-                Ok(_) => {
-                    print_operation_finish();
-                    println!("\n---\nAll files have been reorganized");
                     exit(0);
                 }
                 Err(e) => {
@@ -159,33 +644,95 @@ fn main() {
 
     match matches.subcommand_matches(DIAG_COMMAND) {
         Some(args) => {
+            let format = args.value_of(FORMAT_OPTION).unwrap_or(FORMAT_TEXT_VALUE);
+            let text_format = format == FORMAT_TEXT_VALUE;
+
             let extract_dates_from_path = !args.is_present(DONT_EXTRACT_DATE_FROM_PATH_FLAG);
             info!("extract dates from path: {}", extract_dates_from_path);
-            println!("extract dates from path: {}", extract_dates_from_path);
+
+            if text_format {
+                println!("extract dates from path: {}", extract_dates_from_path);
+            }
 
             let skip_dir_names_for_date_extract: Vec<String> = get_dir_names_skip_masks(args);
 
-            let src_path: &str = args.value_of(SRC_PATH_ARG)
-                                     .expect("invalid value for src-path argument");
+            let src_path: &Path = Path::new(args.value_of_os(SRC_PATH_ARG)
+                                                .expect("invalid value for src-path argument"));
 
-            print_operation_start();
+            let (force_year_for_unknown, year) = get_force_year(args);
+
+            let no_exif_config: NoExifConfig = NoExifConfig {
+                extract_dates_from_path,
+                skip_dir_names_for_date_extract,
+                force_year: force_year_for_unknown,
+                year,
+                fallback: get_fallback(args)
+            };
+
+            let exif_config = get_exif_config(args);
+
+            let diag_config = DiagConfig {
+                template: get_template(args),
+                month_names: get_month_names_from_args(args),
+                with_milliseconds: args.is_present(WITH_MILLISECONDS_FLAG),
+                keep_records: !text_format
+            };
+
+            if text_format {
+                print_operation_start();
+            }
 
-            let ext_filters: Vec<String> = get_extension_filters();
+            let file_filter = get_file_filter(args);
 
-            print!("Getting files list..");
+            let on_progress: fn(usize, usize, usize) = if text_format { show_diag_progress }
+                                                       else { skip_diag_progress };
 
-            match diag_path(src_path, &ext_filters,
-                            extract_dates_from_path,
-                            skip_dir_names_for_date_extract, show_diag_progress) {
+            match diag_path(src_path, &file_filter,
+                            &no_exif_config, &exif_config, &diag_config, on_progress) {
                 Ok(diag_report) => {
-                    println!("\rFiles total: {}", diag_report.files_total);
+                    if format == FORMAT_JSON_VALUE {
+                        println!("{}", diag_report.to_json());
+                        exit(0);
+
+                    } else if format == FORMAT_CSV_VALUE {
+                        print!("{}", diag_report.to_csv());
+                        exit(0);
+                    }
+
+                    println!("\nFiles total: {}", diag_report.files_total);
+                    print_excluded_paths(diag_report.excluded_paths);
+                    print_traversal_errors(&diag_report.traversal_errors);
+                    print_broken_links(&diag_report.broken_links);
+
+                    if !diag_report.date_sources.is_empty() {
+                        println!("---\nDate sources:");
+                        diag_report.date_sources.iter().for_each(|(date_source, files_count)|
+                            println!("{}: {}", date_source, files_count)
+                        );
+                    }
+
+                    if !diag_report.issue_counts.is_empty() {
+                        println!("---\nIssues:");
+                        diag_report.issue_counts.iter().for_each(|(issue, files_count)|
+                            println!("{}: {}", issue, files_count)
+                        );
+                    }
 
                     if diag_report.files_with_issues.is_empty() {
                         println!("---\nAll files are fine. Nothing to do.");
 
                     } else {
                         println!("---\nUnable to determine date for file(s):");
-                        diag_report.files_with_issues.iter().for_each(|file_path| println!("{}", file_path));
+                        diag_report.files_with_issues.iter().for_each(|file_path|
+                            println!("{}", file_path.display())
+                        );
+                    }
+
+                    if !diag_report.files_with_invalid_names.is_empty() {
+                        println!("---\nFile name(s) aren't valid UTF-8:");
+                        diag_report.files_with_invalid_names.iter().for_each(|file_path|
+                            println!("{:?}", file_path)
+                        );
                     }
 
                     print_operation_finish();
@@ -193,7 +740,123 @@ fn main() {
                     exit(0);
                 }
                 Err(e) => {
-                    println!("unable to diagnostic path '{}': {}", src_path, e);
+                    println!("unable to diagnostic path '{}': {}", src_path.display(), e);
+                    exit(ERROR_EXIT_CODE)
+                }
+            }
+        }
+        None => {}
+    }
+
+    match matches.subcommand_matches(DUPES_COMMAND) {
+        Some(args) => {
+            let src_paths: Vec<PathBuf> = args.values_of_os(SRC_PATH_ARG)
+                                              .expect("invalid value for src-path argument")
+                                              .map(PathBuf::from).collect();
+
+            let json_format = args.value_of(FORMAT_OPTION) == Some(FORMAT_JSON_VALUE);
+
+            let file_filter = get_file_filter(args);
+
+            if !json_format {
+                print_operation_start();
+            }
+
+            let on_progress: fn(usize, usize) = if json_format { skip_progress }
+                                                else { show_dupes_progress };
+
+            match find_duplicates(&src_paths, &file_filter, on_progress) {
+                Ok(report) => {
+                    if json_format {
+                        println!("{}", report.to_json());
+                        exit(0);
+                    }
+
+                    println!();
+
+                    report.groups.iter().for_each(|group| {
+                        println!("---\n{} files, {} bytes each:", group.files.len(), group.file_size);
+                        group.files.iter().for_each(|file_path| println!("{}", file_path.display()));
+                    });
+
+                    println!("---\nFiles total: {}", report.files_total);
+                    println!("Duplicate groups: {}", report.groups.len());
+                    println!("Reclaimable space: {} bytes", report.reclaimable_size());
+                    print_excluded_paths(report.excluded_paths);
+                    print_traversal_errors(&report.traversal_errors);
+                    print_broken_links(&report.broken_links);
+
+                    print_operation_finish();
+
+                    exit(0);
+                }
+                Err(e) => {
+                    eprintln!("unable to find duplicates: {}", e);
+                    exit(ERROR_EXIT_CODE)
+                }
+            }
+        }
+        None => {}
+    }
+
+    match matches.subcommand_matches(SIMILAR_COMMAND) {
+        Some(args) => {
+            let src_paths: Vec<PathBuf> = args.values_of_os(SRC_PATH_ARG)
+                                              .expect("invalid value for src-path argument")
+                                              .map(PathBuf::from).collect();
+
+            let json_format = args.value_of(FORMAT_OPTION) == Some(FORMAT_JSON_VALUE);
+
+            let file_filter = get_file_filter(args);
+
+            let max_distance = get_max_distance(args);
+
+            if !json_format {
+                print_operation_start();
+            }
+
+            let on_progress: fn(usize, usize) = if json_format { skip_progress }
+                                                else { show_similar_progress };
+
+            match find_similar(&src_paths, &file_filter, max_distance, on_progress) {
+                Ok(report) => {
+                    if json_format {
+                        println!("{}", report.to_json());
+                        exit(0);
+                    }
+
+                    println!();
+
+                    report.clusters.iter().for_each(|cluster| {
+                        println!("---\nSimilar images: {}", cluster.files.len());
+                        cluster.files.iter().for_each(|image|
+                            println!("{} ({}x{}, {} bytes, distance {})", image.file_path.display(),
+                                     image.width, image.height, image.file_size, image.distance)
+                        );
+                    });
+
+                    println!("---\nFiles total: {}", report.files_total);
+                    println!("Clusters: {}", report.clusters.len());
+
+                    if report.unsupported_files > 0 {
+                        println!("Skipped (not JPEG): {}", report.unsupported_files);
+                    }
+
+                    if !report.decode_errors.is_empty() {
+                        println!("---\nUnable to decode file(s):");
+                        report.decode_errors.iter().for_each(|decode_error| println!("{}", decode_error));
+                    }
+
+                    print_excluded_paths(report.excluded_paths);
+                    print_traversal_errors(&report.traversal_errors);
+                    print_broken_links(&report.broken_links);
+
+                    print_operation_finish();
+
+                    exit(0);
+                }
+                Err(e) => {
+                    eprintln!("unable to find similar images: {}", e);
                     exit(ERROR_EXIT_CODE)
                 }
             }
@@ -215,20 +878,345 @@ fn get_dir_names_skip_masks<'a>(arg_matches: &'a ArgMatches) -> Vec<String> {
     }
 }
 
+fn get_placement_mode(arg_matches: &ArgMatches) -> PlacementMode {
+    if arg_matches.is_present(MOVE_FLAG) {
+        PlacementMode::Move
+
+    } else {
+        match arg_matches.value_of(LINK_OPTION) {
+            Some(LINK_HARD_VALUE) => PlacementMode::HardLink,
+            Some(LINK_SYM_VALUE) => PlacementMode::SymLink,
+            Some(LINK_REFLINK_VALUE) => PlacementMode::Reflink,
+            _ => PlacementMode::Copy
+        }
+    }
+}
+
+fn get_fallback(arg_matches: &ArgMatches) -> Option<FileTimestamp> {
+    match arg_matches.value_of(FALLBACK_OPTION) {
+        Some(FALLBACK_MTIME_VALUE) => Some(FileTimestamp::Modified),
+        Some(FALLBACK_CTIME_VALUE) => Some(FileTimestamp::Changed),
+        Some(FALLBACK_BIRTHTIME_VALUE) => Some(FileTimestamp::Birth),
+        _ => None
+    }
+}
+
+fn get_conflict_policy(arg_matches: &ArgMatches) -> ConflictPolicy {
+    match arg_matches.value_of(ON_CONFLICT_OPTION) {
+        Some(ON_CONFLICT_SKIP_VALUE) => ConflictPolicy::Skip,
+        Some(ON_CONFLICT_OVERWRITE_VALUE) => ConflictPolicy::Overwrite,
+        Some(ON_CONFLICT_FAIL_VALUE) => ConflictPolicy::Fail,
+        Some(ON_CONFLICT_KEEP_LARGER_VALUE) => ConflictPolicy::KeepLarger,
+        _ => ConflictPolicy::Rename
+    }
+}
+
+fn get_month_names_from_args(arg_matches: &ArgMatches) -> MonthNames {
+    let locale = arg_matches.value_of(LOCALE_OPTION).unwrap_or(DEFAULT_LOCALE);
+
+    let month_names = match get_month_names(locale) {
+        Ok(month_names) => month_names,
+        Err(e) => {
+            eprintln!("{}", e);
+            exit(ERROR_EXIT_CODE)
+        }
+    };
+
+    match arg_matches.value_of(MONTH_NAMES_OPTION) {
+        Some(names_str) => {
+            let custom_names: Vec<String> = names_str.split(",")
+                                                     .map(|name| String::from(name))
+                                                     .collect();
+
+            match with_custom_month_names(month_names, &custom_names) {
+                Ok(month_names) => month_names,
+                Err(e) => {
+                    eprintln!("invalid month names: {}", e);
+                    exit(ERROR_EXIT_CODE)
+                }
+            }
+        }
+        None => month_names
+    }
+}
+
+fn get_exif_config(arg_matches: &ArgMatches) -> ExifConfig {
+    let date_tags: Vec<ExifDateTag> = match arg_matches.value_of(EXIF_DATE_TAGS_OPTION) {
+        Some(tags_str) => {
+            tags_str.split(",").map(|tag_name| {
+                match ExifDateTag::from_name(tag_name) {
+                    Some(date_tag) => date_tag,
+                    None => {
+                        eprintln!("unsupported EXIF date tag '{}'", tag_name);
+                        exit(ERROR_EXIT_CODE)
+                    }
+                }
+            }).collect()
+        }
+        None => DEFAULT_DATE_TAGS.to_vec()
+    };
+
+    let timezone = match arg_matches.value_of(TIMEZONE_OPTION) {
+        Some(timezone_str) => {
+            match timezone_str.to_lowercase().as_str() {
+                TIMEZONE_UTC_VALUE => Some(TargetTimeZone::Utc),
+                TIMEZONE_LOCAL_VALUE => Some(TargetTimeZone::Local),
+                _ => match parse_utc_offset(timezone_str) {
+                    Some(offset) => Some(TargetTimeZone::Fixed(offset)),
+                    None => {
+                        eprintln!("unsupported time zone '{}'", timezone_str);
+                        exit(ERROR_EXIT_CODE)
+                    }
+                }
+            }
+        }
+        None => None
+    };
+
+    ExifConfig { date_tags, timezone, time_shift: get_time_shift_config(arg_matches) }
+}
+
+fn get_time_shift_config(arg_matches: &ArgMatches) -> TimeShiftConfig {
+    let default_shift = match arg_matches.value_of(TIME_SHIFT_OPTION) {
+        Some(shift_str) => match parse_time_shift(shift_str) {
+            Some(shift) => Some(shift),
+            None => {
+                eprintln!("invalid time shift '{}', expected format: [+|-]HH:MM[:SS], \
+                           hours up to {}", shift_str, MAX_TIME_SHIFT_HOURS);
+                exit(ERROR_EXIT_CODE)
+            }
+        },
+        None => None
+    };
+
+    let rules = match arg_matches.value_of(TIME_SHIFT_RULES_OPTION) {
+        Some(rules_file_path) => match load_time_shift_rules(rules_file_path) {
+            Ok(rules) => rules,
+            Err(e) => {
+                eprintln!("invalid time shift rules: {}", e);
+                exit(ERROR_EXIT_CODE)
+            }
+        },
+        None => Vec::new()
+    };
+
+    TimeShiftConfig { rules, default_shift }
+}
+
+/// Supported extensions by default
 fn get_extension_filters() -> Vec<String> {
-    vec![String::from("jpg"), String::from("jpeg"), String::from("tiff")]
+    IMAGE_EXTENSIONS.iter()
+        .chain(RAW_EXTENSIONS.iter())
+        .chain(VIDEO_EXTENSIONS.iter())
+        .map(|ext| String::from(*ext))
+        .collect()
+}
+
+fn get_file_filter(arg_matches: &ArgMatches) -> FileFilter {
+    let mut extensions = get_extension_filters();
+
+    for ext in get_extensions_from_arg(arg_matches, INCLUDE_EXT_OPTION) {
+        if !extensions.contains(&ext) {
+            extensions.push(ext);
+        }
+    }
+
+    let excluded_extensions = get_extensions_from_arg(arg_matches, EXCLUDE_EXT_OPTION);
+    extensions.retain(|ext| !excluded_extensions.contains(ext));
+
+    if extensions.is_empty() {
+        eprintln!("all extensions have been excluded, nothing to process");
+        exit(ERROR_EXIT_CODE)
+    }
+
+    let exclude_patterns: Vec<String> = match arg_matches.values_of(EXCLUDE_OPTION) {
+        Some(values) => values.map(String::from).collect(),
+        None => Vec::new()
+    };
+
+    let exclude_rules = match parse_exclude_patterns(&exclude_patterns) {
+        Ok(exclude_rules) => exclude_rules,
+        Err(e) => {
+            eprintln!("invalid exclude pattern: {}", e);
+            exit(ERROR_EXIT_CODE)
+        }
+    };
+
+    FileFilter {
+        extensions, sniff_content: arg_matches.is_present(SNIFF_CONTENT_FLAG), exclude_rules,
+        follow_symlinks: arg_matches.is_present(FOLLOW_SYMLINKS_FLAG)
+    }
+}
+
+/// Extensions separated with comma, case insensitive, leading dot is optional.
+fn get_extensions_from_arg(arg_matches: &ArgMatches, arg_name: &str) -> Vec<String> {
+    match arg_matches.value_of(arg_name) {
+        Some(value) => value.split(',')
+                            .map(|ext| ext.trim().trim_start_matches('.').to_lowercase())
+                            .filter(|ext| !ext.is_empty())
+                            .collect(),
+        None => Vec::new()
+    }
+}
+
+fn get_jobs(args: &ArgMatches) -> usize {
+    let value_str = args.value_of(JOBS_OPTION).unwrap_or(JOBS_DEFAULT_VALUE);
+
+    match value_str.parse::<usize>() {
+        Ok(jobs) if jobs > 0 => jobs,
+        _ => {
+            eprintln!("invalid jobs count '{}', positive number expected", value_str);
+            exit(ERROR_EXIT_CODE)
+        }
+    }
+}
+
+fn get_template(args: &ArgMatches) -> PathTemplate {
+    let template_str = args.value_of(TEMPLATE_OPTION).unwrap_or(DEFAULT_TEMPLATE);
+
+    match parse_template(template_str) {
+        Ok(template) => template,
+        Err(e) => {
+            eprintln!("invalid template '{}': {}", template_str, e);
+            exit(ERROR_EXIT_CODE)
+        }
+    }
+}
+
+/// Returns `(true, year)` if year is forced for files without date.
+fn get_force_year(args: &ArgMatches) -> (bool, i32) {
+    match args.value_of(FORCE_YEAR_OPTION) {
+        Some(value_str) => {
+            let year: i32 = value_str.parse::<i32>().unwrap();
+            (true, year)
+        }
+        None => (false, 0)
+    }
+}
+
+fn get_max_distance(args: &ArgMatches) -> u32 {
+    match args.value_of(MAX_DISTANCE_OPTION) {
+        Some(value_str) => {
+            match value_str.parse::<u32>() {
+                Ok(max_distance) if max_distance <= 64 => max_distance,
+                _ => {
+                    eprintln!("invalid max distance '{}', number 0..64 expected", value_str);
+                    exit(ERROR_EXIT_CODE)
+                }
+            }
+        }
+        None => DEFAULT_MAX_DISTANCE
+    }
+}
+
+fn print_excluded_paths(excluded_paths: usize) {
+    if excluded_paths > 0 {
+        println!("Excluded paths: {}", excluded_paths);
+    }
+}
+
+fn print_collisions(report: &ReorganizeReport) {
+    if !report.collisions.is_empty() {
+        println!("\n---\nCollisions: {}", report.collisions.len());
+        report.collisions.iter().for_each(|collision|
+            println!("{} -> {}: {}", collision.src_file_path.display(),
+                     collision.dest_file_path.display(), collision.decision)
+        );
+    }
+}
+
+fn print_duplicates(report: &ReorganizeReport) {
+    if !report.duplicates.is_empty() {
+        println!("\n---\nDuplicates (skipped): {}", report.duplicates.len());
+        report.duplicates.iter().for_each(|duplicate|
+            println!("{} (copy of {})", duplicate.src_file_path.display(),
+                     duplicate.original_file_path.display())
+        );
+    }
 }
 
-fn show_reorganize_progress(total_elements: usize, current_element_index: usize) {
+fn print_left_in_place(report: &ReorganizeReport) {
+    if !report.left_in_place.is_empty() {
+        println!("\n---\nLeft in place (destination has the same content): {}",
+                 report.left_in_place.len());
+        report.left_in_place.iter().for_each(|file_path| println!("{}", file_path.display()));
+    }
+}
+
+fn print_undated_files(report: &ReorganizeReport) {
+    let copied_files: Vec<&UndatedFile> = report.undated_files.iter()
+        .filter(|undated_file| undated_file.unknown_file_path.is_some())
+        .collect();
+
+    if !copied_files.is_empty() {
+        println!("\n---\nFiles without date (copied to unknown dir): {}", copied_files.len());
+        copied_files.iter().for_each(|undated_file|
+            if let Some(unknown_file_path) = &undated_file.unknown_file_path {
+                println!("{} -> {}", undated_file.src_file_path.display(),
+                         unknown_file_path.display())
+            }
+        );
+    }
+
+    let left_files: Vec<&UndatedFile> = report.undated_files.iter()
+        .filter(|undated_file| undated_file.unknown_file_path.is_none())
+        .collect();
+
+    if !left_files.is_empty() {
+        println!("\n---\nUnable to determine date for file(s), left in source path: {}",
+                 left_files.len());
+        left_files.iter().for_each(|undated_file|
+            println!("{}", undated_file.src_file_path.display())
+        );
+    }
+}
+
+fn print_failed_files(report: &ReorganizeReport) {
+    if !report.failed_files.is_empty() {
+        println!("\n---\nUnable to place file(s):");
+        report.failed_files.iter().for_each(|file_path| println!("{}", file_path.display()));
+    }
+}
+
+fn print_traversal_errors(traversal_errors: &Vec<String>) {
+    if !traversal_errors.is_empty() {
+        println!("---\nUnable to read path(s):");
+        traversal_errors.iter().for_each(|traversal_error| println!("{}", traversal_error));
+    }
+}
+
+fn print_broken_links(broken_links: &Vec<PathBuf>) {
+    if !broken_links.is_empty() {
+        println!("---\nBroken symbolic link(s):");
+        broken_links.iter().for_each(|broken_link| println!("{}", broken_link.display()));
+    }
+}
+
+fn show_reorganize_progress(discovered_files: usize, processed_files: usize) {
     print!("\r");
-    print!("Progress: {}/{}", current_element_index, total_elements);
+    print!("Progress: {} (discovered so far: {})", processed_files, discovered_files);
 }
 
-fn show_diag_progress(total_elements: usize, current_element_index: usize, with_issues: usize) {
+fn show_diag_progress(discovered_files: usize, processed_files: usize, with_issues: usize) {
     print!("\r");
-    print!("Progress: {}/{} (with issues: {})", current_element_index, total_elements, with_issues);
+    print!("Progress: {} (discovered so far: {}, with issues: {})",
+           processed_files, discovered_files, with_issues);
 }
 
+fn show_dupes_progress(discovered_files: usize, hashed_files: usize) {
+    print!("\r");
+    print!("Progress: {} (discovered so far: {})", hashed_files, discovered_files);
+}
+
+fn show_similar_progress(discovered_files: usize, processed_files: usize) {
+    print!("\r");
+    print!("Progress: {} (discovered so far: {})", processed_files, discovered_files);
+}
+
+fn skip_progress(_discovered_files: usize, _processed_files: usize) {}
+
+fn skip_diag_progress(_discovered_files: usize, _processed_files: usize, _with_issues: usize) {}
+
 fn print_operation_start() {
     print_operation_datetime("Started")
 }
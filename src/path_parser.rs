@@ -3,6 +3,8 @@ pub mod path_parser {
     use regex::Regex;
     use std::path::PathBuf;
 
+    use crate::domain::domain::PathDatePattern;
+
     const SOLID_DATE_FORMAT: &str = "%Y%m%d";
     const SOLID_REGEX_PATTERN: &str = "(\\d{4}\\d{2}\\d{2})";
 
@@ -12,20 +14,22 @@ pub mod path_parser {
     const DATE_FORMAT_WITH_DOTS: &str = "%Y.%m.%d";
     const DATE_PATTERN_WITH_DOTS: &str = "(\\d{4}.\\d{2}.\\d{2})";
 
-    pub fn get_dates_from_path(path: &str) -> Vec<NaiveDate> {
-        let mut results: Vec<NaiveDate> = Vec::new();
+    /// Dates found in path along with the pattern which matched them.
+    pub fn get_dates_from_path(path: &str) -> Vec<(NaiveDate, PathDatePattern)> {
+        let mut results: Vec<(NaiveDate, PathDatePattern)> = Vec::new();
 
         info!("extract date form path '{}'", path);
 
-        let date_formats: Vec<(&str, &str)> = vec![
-            (SOLID_REGEX_PATTERN, SOLID_DATE_FORMAT),
-            (DATE_PATTERN_WITH_HYPHENS, DATE_FORMAT_WITH_HYPHENS),
-            (DATE_PATTERN_WITH_DOTS, DATE_FORMAT_WITH_DOTS)
+        let date_formats: [(&str, &str, PathDatePattern); 3] = [
+            (SOLID_REGEX_PATTERN, SOLID_DATE_FORMAT, PathDatePattern::Solid),
+            (DATE_PATTERN_WITH_HYPHENS, DATE_FORMAT_WITH_HYPHENS, PathDatePattern::WithHyphens),
+            (DATE_PATTERN_WITH_DOTS, DATE_FORMAT_WITH_DOTS, PathDatePattern::WithDots)
         ];
 
         for date_format in date_formats.iter() {
             results.extend(
             extract_dates_from_path(date_format.0, date_format.1, path)
+                .into_iter().map(|date| (date, date_format.2))
             );
         }
 
@@ -1,6 +1,7 @@
 #[cfg(test)]
 pub mod path_parser_tests {
     use crate::path_parser::path_parser::{get_dates_from_path, get_path_without_dir_names};
+    use crate::domain::domain::PathDatePattern;
     use chrono::{Datelike, NaiveDate};
 
     #[test]
@@ -47,14 +48,24 @@ pub mod path_parser_tests {
         assert!(vec_contains_date(&results, 2017, 2, 11));
     }
 
+    #[test]
+    fn matched_pattern_should_be_returned_with_date() {
+        let results = get_dates_from_path("/mnt/pics/20191123/2017-02-11_IMG_14523.jpg");
+
+        assert_eq!(results, vec![
+            (NaiveDate::from_ymd_opt(2019, 11, 23).unwrap(), PathDatePattern::Solid),
+            (NaiveDate::from_ymd_opt(2017, 2, 11).unwrap(), PathDatePattern::WithHyphens)
+        ]);
+    }
+
     #[test]
     fn invalid_dates_should_be_ignored() {
         let results = get_dates_from_path("/mnt/pics/20196229/2019.62.49/2017-02-99_IMG13.jpg");
         assert_eq!(results.len(), 0);
     }
 
-    fn vec_contains_date(vec: &Vec<NaiveDate>, year: i32, month: u32, day: u32) -> bool {
-        let date_found = vec.iter().find(|date| {
+    fn vec_contains_date(vec: &Vec<(NaiveDate, PathDatePattern)>, year: i32, month: u32, day: u32) -> bool {
+        let date_found = vec.iter().find(|(date, _)| {
                 date.year() == year && date.month() == month && date.day() == day
             }
         );
@@ -0,0 +1,339 @@
+pub mod similar {
+    use std::{io, fs};
+    use std::fs::File;
+    use std::io::BufReader;
+    use std::path::{Path, PathBuf};
+    use std::collections::HashSet;
+    use jpeg_decoder::{Decoder, PixelFormat};
+    use serde_json::{json, Value};
+    use crate::files::files::{walk_files, read_file_header};
+    use crate::domain::domain::FileFilter;
+
+    /// dHash compares neighbour pixels of grayscale thumbnail 9x8, 64 bits
+    const DHASH_WIDTH: usize = 9;
+    const DHASH_HEIGHT: usize = 8;
+
+    /// JPEG is decoded with DCT scaling to thumbnail not smaller than this size,
+    /// much faster than full decoding
+    const DECODE_SIZE: u16 = 64;
+
+    const JPEG_SIGNATURE: [u8; 3] = [0xFF, 0xD8, 0xFF];
+
+    pub const DEFAULT_MAX_DISTANCE: u32 = 10;
+
+    /// Perceptual fingerprint of image and its original size.
+    pub struct ImageFingerprint {
+        pub hash: u64,
+        pub width: u32,
+        pub height: u32
+    }
+
+    pub struct SimilarImage {
+        pub file_path: PathBuf,
+        pub width: u32,
+        pub height: u32,
+        pub file_size: u64,
+
+        /// Hamming distance to fingerprint of cluster original
+        pub distance: u32,
+
+        hash: u64
+    }
+
+    /// Visually similar images, the first one is the original: the highest resolution,
+    /// then the largest file.
+    pub struct SimilarCluster {
+        pub files: Vec<SimilarImage>
+    }
+
+    pub struct SimilarReport {
+        pub files_total: usize,
+
+        /// Clusters with two or more images
+        pub clusters: Vec<SimilarCluster>,
+
+        /// Files which aren't JPEG
+        pub unsupported_files: usize,
+
+        /// JPEG files which couldn't be decoded
+        pub decode_errors: Vec<String>,
+
+        /// Files and directories skipped by exclusion rules
+        pub excluded_paths: usize,
+
+        /// Directories and entries which couldn't be read, traversal has been continued
+        pub traversal_errors: Vec<String>,
+
+        /// Symbolic links with missing target
+        pub broken_links: Vec<PathBuf>
+    }
+
+    impl SimilarReport {
+        pub fn to_json(&self) -> Value {
+            json!({
+                "files_total": self.files_total,
+                "clusters": self.clusters.iter().map(|cluster| json!({
+                    "original": cluster.files[0].file_path.to_string_lossy(),
+                    "files": cluster.files.iter().map(|image| json!({
+                        "path": image.file_path.to_string_lossy(),
+                        "width": image.width,
+                        "height": image.height,
+                        "size": image.file_size,
+                        "distance": image.distance
+                    })).collect::<Vec<Value>>()
+                })).collect::<Vec<Value>>(),
+                "unsupported_files": self.unsupported_files,
+                "decode_errors": self.decode_errors,
+                "excluded_paths": self.excluded_paths,
+                "traversal_errors": self.traversal_errors,
+                "broken_links": self.broken_links.iter()
+                                                 .map(|file_path| file_path.to_string_lossy())
+                                                 .collect::<Vec<_>>()
+            })
+        }
+    }
+
+    /// Find JPEG images which differ by size or compression only. Images are clustered around
+    /// originals: each image joins the first cluster which original is within `max_distance`.
+    pub fn find_similar(src_paths: &Vec<PathBuf>, file_filter: &FileFilter, max_distance: u32,
+                        on_progress: fn(discovered: usize, processed: usize))
+                                                        -> Result<SimilarReport, io::Error> {
+        info!("find similar images in paths: {:?}", src_paths);
+        info!("file filter: {}", file_filter.to_string());
+        info!("max distance: {}", max_distance);
+
+        let mut report = SimilarReport {
+            files_total: 0,
+            clusters: Vec::new(),
+            unsupported_files: 0,
+            decode_errors: Vec::new(),
+            excluded_paths: 0,
+            traversal_errors: Vec::new(),
+            broken_links: Vec::new()
+        };
+
+        let mut images: Vec<SimilarImage> = Vec::new();
+
+        // source paths might overlap
+        let mut known_files: HashSet<PathBuf> = HashSet::new();
+
+        let mut processed_files: usize = 0;
+
+        for src_path in src_paths {
+            let mut walker = match walk_files(src_path, file_filter) {
+                Ok(walker) => walker,
+                Err(e) => {
+                    error!("unable to get files from path '{}': {}", src_path.display(), e);
+                    return Err(e)
+                }
+            };
+
+            for item in walker.by_ref() {
+                let file_path = match item {
+                    Ok(file_path) => file_path,
+                    Err(e) => {
+                        error!("traversal error: {}", e);
+                        report.traversal_errors.push(e);
+                        continue
+                    }
+                };
+
+                let canonical_path = fs::canonicalize(&file_path).unwrap_or(file_path.clone());
+
+                if !known_files.insert(canonical_path) {
+                    debug!("file '{}' has been already found", file_path.display());
+                    continue
+                }
+
+                report.files_total += 1;
+
+                match get_similar_image(&file_path) {
+                    Ok(Some(image)) => images.push(image),
+                    Ok(None) => {
+                        debug!("file '{}' isn't JPEG, skip", file_path.display());
+                        report.unsupported_files += 1;
+                    }
+                    Err(e) => {
+                        error!("unable to decode '{}': {}", file_path.display(), e);
+                        report.decode_errors.push(format!("{}: {}", file_path.display(), e));
+                    }
+                }
+
+                processed_files += 1;
+                on_progress(report.files_total, processed_files);
+            }
+
+            report.excluded_paths += walker.excluded_paths;
+            report.broken_links.append(&mut walker.broken_links);
+        }
+
+        report.clusters = get_clusters(images, max_distance);
+
+        Ok(report)
+    }
+
+    fn get_similar_image(file_path: &Path) -> Result<Option<SimilarImage>, String> {
+        let header = read_file_header(file_path).map_err(|e| e.to_string())?;
+
+        if !header.starts_with(&JPEG_SIGNATURE) {
+            return Ok(None)
+        }
+
+        let fingerprint = get_image_fingerprint(file_path)?;
+        let file_size = fs::metadata(file_path).map_err(|e| e.to_string())?.len();
+
+        Ok(
+            Some(SimilarImage {
+                file_path: file_path.to_path_buf(),
+                width: fingerprint.width,
+                height: fingerprint.height,
+                file_size,
+                distance: 0,
+                hash: fingerprint.hash
+            })
+        )
+    }
+
+    fn get_clusters(mut images: Vec<SimilarImage>, max_distance: u32) -> Vec<SimilarCluster> {
+        images.sort_by(|first, second|
+            (second.width as u64 * second.height as u64)
+                .cmp(&(first.width as u64 * first.height as u64))
+                .then_with(|| second.file_size.cmp(&first.file_size))
+                .then_with(|| first.file_path.cmp(&second.file_path))
+        );
+
+        let mut clusters: Vec<SimilarCluster> = Vec::new();
+
+        for mut image in images {
+            let cluster = clusters.iter_mut().find(|cluster|
+                get_hamming_distance(cluster.files[0].hash, image.hash) <= max_distance
+            );
+
+            match cluster {
+                Some(cluster) => {
+                    image.distance = get_hamming_distance(cluster.files[0].hash, image.hash);
+                    cluster.files.push(image);
+                }
+                None => clusters.push(SimilarCluster { files: vec![image] })
+            }
+        }
+
+        clusters.retain(|cluster| cluster.files.len() > 1);
+
+        for cluster in clusters.iter() {
+            info!("similar images: {:?}", cluster.files.iter()
+                                                      .map(|image| &image.file_path)
+                                                      .collect::<Vec<_>>());
+        }
+
+        clusters
+    }
+
+    /// Difference hash (dHash) of JPEG image.
+    pub fn get_image_fingerprint(file_path: &Path) -> Result<ImageFingerprint, String> {
+        let file = File::open(file_path).map_err(|e| e.to_string())?;
+        let mut decoder = Decoder::new(BufReader::new(file));
+
+        decoder.read_info().map_err(|e| e.to_string())?;
+        let original_info = decoder.info().ok_or("no image info")?;
+
+        decoder.scale(DECODE_SIZE, DECODE_SIZE).map_err(|e| e.to_string())?;
+
+        let pixels = decoder.decode().map_err(|e| e.to_string())?;
+        let info = decoder.info().ok_or("no image info")?;
+
+        let width = info.width as usize;
+        let height = info.height as usize;
+
+        if width == 0 || height == 0 {
+            return Err(String::from("empty image"))
+        }
+
+        let gray = get_gray_pixels(&pixels, info.pixel_format);
+
+        if gray.len() < width * height {
+            return Err(String::from("incomplete image data"))
+        }
+
+        let thumbnail = resize(&gray, width, height, DHASH_WIDTH, DHASH_HEIGHT);
+
+        let mut hash: u64 = 0;
+
+        for y in 0..DHASH_HEIGHT {
+            for x in 0..DHASH_WIDTH - 1 {
+                hash <<= 1;
+
+                if thumbnail[y * DHASH_WIDTH + x] < thumbnail[y * DHASH_WIDTH + x + 1] {
+                    hash |= 1;
+                }
+            }
+        }
+
+        Ok(
+            ImageFingerprint {
+                hash,
+                width: original_info.width as u32,
+                height: original_info.height as u32
+            }
+        )
+    }
+
+    pub fn get_hamming_distance(first_hash: u64, second_hash: u64) -> u32 {
+        (first_hash ^ second_hash).count_ones()
+    }
+
+    fn get_gray_pixels(pixels: &[u8], pixel_format: PixelFormat) -> Vec<u8> {
+        match pixel_format {
+            PixelFormat::L8 => pixels.to_vec(),
+
+            // big endian, high byte is enough
+            PixelFormat::L16 => pixels.chunks(2).map(|pixel| pixel[0]).collect(),
+
+            PixelFormat::RGB24 => pixels.chunks(3)
+                                        .filter(|pixel| pixel.len() == 3)
+                                        .map(|pixel| get_luma(pixel[0], pixel[1], pixel[2]))
+                                        .collect(),
+
+            PixelFormat::CMYK32 => pixels.chunks(4)
+                .filter(|pixel| pixel.len() == 4)
+                .map(|pixel| {
+                    let black = 255 - pixel[3] as u32;
+                    let channel = |value: u8| ((255 - value as u32) * black / 255) as u8;
+                    get_luma(channel(pixel[0]), channel(pixel[1]), channel(pixel[2]))
+                })
+                .collect()
+        }
+    }
+
+    fn get_luma(red: u8, green: u8, blue: u8) -> u8 {
+        ((red as u32 * 299 + green as u32 * 587 + blue as u32 * 114) / 1000) as u8
+    }
+
+    /// Resize grayscale image with box filter: each target pixel is average of source area.
+    fn resize(pixels: &[u8], width: usize, height: usize,
+              target_width: usize, target_height: usize) -> Vec<u32> {
+        let mut results: Vec<u32> = Vec::with_capacity(target_width * target_height);
+
+        for target_y in 0..target_height {
+            let start_y = target_y * height / target_height;
+            let end_y = ((target_y + 1) * height / target_height).max(start_y + 1).min(height);
+
+            for target_x in 0..target_width {
+                let start_x = target_x * width / target_width;
+                let end_x = ((target_x + 1) * width / target_width).max(start_x + 1).min(width);
+
+                let mut sum: u32 = 0;
+
+                for y in start_y..end_y {
+                    for x in start_x..end_x {
+                        sum += pixels[y * width + x] as u32;
+                    }
+                }
+
+                results.push(sum / ((end_y - start_y) * (end_x - start_x)) as u32);
+            }
+        }
+
+        results
+    }
+}
@@ -0,0 +1,118 @@
+#[cfg(test)]
+pub mod similar_tests {
+    use std::{env, fs};
+    use std::fs::File;
+    use std::path::{Path, PathBuf};
+    use jpeg_decoder::{Decoder, PixelFormat};
+    use jpeg_encoder::{Encoder, ColorType};
+    use crate::similar::similar::{find_similar, get_image_fingerprint, get_hamming_distance,
+                                  DEFAULT_MAX_DISTANCE};
+    use crate::domain::domain::FileFilter;
+    use crate::exclusions::exclusions::ExcludeRules;
+    use crate::get_extension_filters;
+
+    const SAMPLE_FILE_PATH: &str = "img-src/20201010/IMG_20201010_120947.jpg";
+    const OTHER_FILE_PATH: &str = "img-src/exif-without-date/20130517/Subfolder/manga__berserk__forest.jpg";
+
+    #[test]
+    fn resized_copy_should_have_close_fingerprint() {
+        let test_dir = get_test_dir("resized_copy_should_have_close_fingerprint");
+
+        let resized_file_path = test_dir.join("resized.jpg");
+        write_resized_copy(SAMPLE_FILE_PATH, &resized_file_path);
+
+        let original = get_image_fingerprint(Path::new(SAMPLE_FILE_PATH)).unwrap();
+        let resized = get_image_fingerprint(&resized_file_path).unwrap();
+        let other = get_image_fingerprint(Path::new(OTHER_FILE_PATH)).unwrap();
+
+        assert_eq!(resized.width, original.width / 2);
+        assert!(get_hamming_distance(original.hash, resized.hash) <= DEFAULT_MAX_DISTANCE);
+        assert!(get_hamming_distance(original.hash, other.hash) > DEFAULT_MAX_DISTANCE);
+
+        fs::remove_dir_all(&test_dir).unwrap();
+    }
+
+    #[test]
+    fn similar_images_should_be_clustered_around_original() {
+        let test_dir = get_test_dir("similar_images_should_be_clustered_around_original");
+
+        fs::create_dir_all(test_dir.join("whatsapp")).unwrap();
+
+        fs::copy(SAMPLE_FILE_PATH, test_dir.join("IMG_0001.jpg")).unwrap();
+        fs::copy(OTHER_FILE_PATH, test_dir.join("forest.jpg")).unwrap();
+        write_resized_copy(SAMPLE_FILE_PATH, &test_dir.join("whatsapp/IMG-WA0001.jpg"));
+        fs::write(test_dir.join("broken.jpg"), "not a jpeg").unwrap();
+
+        let report = find_similar(&vec![test_dir.clone()], &get_file_filter(),
+                                  DEFAULT_MAX_DISTANCE, show_progress).unwrap();
+
+        assert_eq!(report.files_total, 4);
+        assert_eq!(report.unsupported_files, 1);
+        assert_eq!(report.clusters.len(), 1);
+
+        let files = &report.clusters[0].files;
+        assert_eq!(files.len(), 2);
+        assert_eq!(files[0].file_path, test_dir.join("IMG_0001.jpg"));
+        assert_eq!(files[0].distance, 0);
+        assert_eq!(files[1].file_path, test_dir.join("whatsapp/IMG-WA0001.jpg"));
+
+        let json = report.to_json();
+        assert_eq!(json["clusters"][0]["original"], files[0].file_path.to_str().unwrap());
+
+        fs::remove_dir_all(&test_dir).unwrap();
+    }
+
+    /// Half-size copy with stronger compression, like messenger recompression.
+    fn write_resized_copy(src_file_path: &str, dest_file_path: &Path) {
+        let mut decoder = Decoder::new(File::open(src_file_path).unwrap());
+        let pixels = decoder.decode().unwrap();
+        let info = decoder.info().unwrap();
+        assert_eq!(info.pixel_format, PixelFormat::RGB24);
+
+        let width = info.width as usize;
+        let height = info.height as usize;
+        let (resized_width, resized_height) = (width / 2, height / 2);
+
+        let mut resized: Vec<u8> = Vec::with_capacity(resized_width * resized_height * 3);
+
+        for y in 0..resized_height {
+            for x in 0..resized_width {
+                for channel in 0..3 {
+                    let sum: u32 = [(0, 0), (1, 0), (0, 1), (1, 1)].iter()
+                        .map(|(dx, dy)| pixels[((y * 2 + dy) * width + x * 2 + dx) * 3 + channel] as u32)
+                        .sum();
+                    resized.push((sum / 4) as u8);
+                }
+            }
+        }
+
+        let encoder = Encoder::new_file(dest_file_path, 40).unwrap();
+        encoder.encode(&resized, resized_width as u16, resized_height as u16, ColorType::Rgb).unwrap();
+    }
+
+    fn get_file_filter() -> FileFilter {
+        FileFilter {
+            extensions: get_extension_filters(),
+            sniff_content: false,
+            exclude_rules: ExcludeRules::new(),
+            follow_symlinks: false
+        }
+    }
+
+    /// Empty directory for test files, should be removed at the end of test.
+    fn get_test_dir(test_name: &str) -> PathBuf {
+        let dir_path = env::temp_dir().join("imgtag-tests").join(test_name);
+
+        if dir_path.exists() {
+            fs::remove_dir_all(&dir_path).unwrap();
+        }
+
+        fs::create_dir_all(&dir_path).unwrap();
+        dir_path
+    }
+
+    fn show_progress(discovered_files: usize, processed_files: usize) {
+        print!("\r");
+        print!("progress: {} (discovered: {})", processed_files, discovered_files);
+    }
+}
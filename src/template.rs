@@ -0,0 +1,260 @@
+pub mod template {
+    use chrono::{NaiveDate, NaiveTime, Datelike};
+    use std::ffi::{OsStr, OsString};
+    use std::path::Path;
+    use crate::domain::domain::DateSource;
+
+    /// Layout `YYYY/Month/YYYY-MM-DD__HH-MM-SS__filename.jpg`
+    pub const DEFAULT_TEMPLATE: &str = "{year}/{month_name}/{datetime}__{original_name}";
+
+    const DEST_DATETIME_FORMAT: &str = "%Y-%m-%d__%H-%M-%S";
+    const DEST_DATETIME_WITH_MILLISECONDS_FORMAT: &str = "%Y-%m-%d__%H-%M-%S-%3f";
+    const DEST_DATE_FORMAT: &str = "%Y-%m-%d";
+
+    const UNKNOWN_CAMERA_MODEL: &str = "Unknown";
+
+    /// Time placeholders for dates without time (from path or forced year)
+    const UNKNOWN_TIME: &str = "unknown";
+
+    const PATH_SEPARATOR: char = '/';
+
+    /// Characters which can't be used in file names on popular filesystems
+    const FORBIDDEN_CHARS: [char; 9] = ['/', '\\', ':', '*', '?', '"', '<', '>', '|'];
+
+    #[derive(Clone, Copy, PartialEq, Debug)]
+    enum Placeholder {
+        Year,
+        Quarter,
+        Month,
+        MonthName,
+        Day,
+        Hour,
+        Minute,
+        Second,
+        Millisecond,
+        Date,
+        DateTime,
+        CameraModel,
+        OriginalName,
+        Name,
+        Ext,
+        DateSource
+    }
+
+    #[derive(Clone, PartialEq, Debug)]
+    enum TemplatePart {
+        Text(String),
+        Placeholder(Placeholder)
+    }
+
+    /// Destination path template, relative to destination directory.
+    /// Example: `{year}/{month}/{day}/{original_name}`
+    pub struct PathTemplate {
+        source: String,
+        parts: Vec<TemplatePart>
+    }
+
+    /// Values for template placeholders.
+    pub struct TemplateValues<'a> {
+        pub date: NaiveDate,
+
+        /// Unknown for dates extracted from path or forced year
+        pub time: Option<NaiveTime>,
+
+        pub month_name: &'a str,
+        pub camera_model: Option<&'a str>,
+
+        /// Kept as is, might be not valid UTF-8
+        pub original_name: &'a OsStr,
+
+        pub date_source: DateSource,
+
+        /// Include milliseconds into `{datetime}` if time is known
+        pub with_milliseconds: bool
+    }
+
+    impl PathTemplate {
+        pub fn to_string(&self) -> String {
+            self.source.clone()
+        }
+
+        /// Render relative file path. Parts are separated with `/`.
+        pub fn render(&self, values: &TemplateValues) -> OsString {
+            let mut result = OsString::new();
+
+            for part in self.parts.iter() {
+                match part {
+                    TemplatePart::Text(text) => result.push(text),
+                    TemplatePart::Placeholder(placeholder) =>
+                        result.push(render_placeholder(*placeholder, values))
+                }
+            }
+
+            result
+        }
+    }
+
+    /// Parse and validate template.
+    pub fn parse_template(template: &str) -> Result<PathTemplate, String> {
+        if template.trim().is_empty() {
+            return Err(String::from("template is empty"))
+        }
+
+        if template.starts_with(PATH_SEPARATOR) || template.starts_with('\\') {
+            return Err(String::from("template should be relative to destination path"))
+        }
+
+        let mut parts: Vec<TemplatePart> = Vec::new();
+        let mut text = String::new();
+        let mut placeholder_name: Option<String> = None;
+
+        for c in template.chars() {
+            match placeholder_name.as_mut() {
+                Some(name) => {
+                    if c == '}' {
+                        parts.push(TemplatePart::Placeholder(get_placeholder(name)?));
+                        placeholder_name = None;
+
+                    } else if c == '{' {
+                        return Err(format!("unexpected '{{' in placeholder '{}'", name))
+
+                    } else {
+                        name.push(c);
+                    }
+                }
+                None => {
+                    if c == '{' {
+                        if !text.is_empty() {
+                            parts.push(TemplatePart::Text(text.clone()));
+                            text.clear();
+                        }
+
+                        placeholder_name = Some(String::new());
+
+                    } else if c == '}' {
+                        return Err(String::from("unexpected '}' outside of placeholder"))
+
+                    } else {
+                        text.push(c);
+                    }
+                }
+            }
+        }
+
+        if let Some(name) = placeholder_name {
+            return Err(format!("placeholder '{}' isn't closed", name))
+        }
+
+        if !text.is_empty() {
+            parts.push(TemplatePart::Text(text));
+        }
+
+        validate_path_segments(template)?;
+
+        let file_name_part = template.rsplit(PATH_SEPARATOR).next().unwrap_or("");
+
+        if !file_name_part.contains('{') {
+            return Err(String::from("file name part of template should contain placeholders, \
+                                     for example {original_name}"))
+        }
+
+        Ok(PathTemplate { source: String::from(template), parts })
+    }
+
+    fn validate_path_segments(template: &str) -> Result<(), String> {
+        for segment in template.split(PATH_SEPARATOR) {
+            if segment.is_empty() {
+                return Err(String::from("template contains empty path segment"))
+            }
+
+            if segment == "." || segment == ".." {
+                return Err(format!("unsupported path segment '{}'", segment))
+            }
+        }
+
+        Ok(())
+    }
+
+    fn get_placeholder(name: &str) -> Result<Placeholder, String> {
+        match name {
+            "year" => Ok(Placeholder::Year),
+            "quarter" => Ok(Placeholder::Quarter),
+            "month" => Ok(Placeholder::Month),
+            "month_name" => Ok(Placeholder::MonthName),
+            "day" => Ok(Placeholder::Day),
+            "hour" => Ok(Placeholder::Hour),
+            "minute" => Ok(Placeholder::Minute),
+            "second" => Ok(Placeholder::Second),
+            "millisecond" => Ok(Placeholder::Millisecond),
+            "date" => Ok(Placeholder::Date),
+            "datetime" => Ok(Placeholder::DateTime),
+            "camera_model" => Ok(Placeholder::CameraModel),
+            "original_name" => Ok(Placeholder::OriginalName),
+            "name" => Ok(Placeholder::Name),
+            "ext" => Ok(Placeholder::Ext),
+            "date_source" => Ok(Placeholder::DateSource),
+            _ => Err(format!("unknown placeholder '{{{}}}'", name))
+        }
+    }
+
+    fn render_placeholder(placeholder: Placeholder, values: &TemplateValues) -> OsString {
+        match placeholder {
+            Placeholder::OriginalName => values.original_name.to_os_string(),
+            Placeholder::Name => match Path::new(values.original_name).file_stem() {
+                Some(name) => name.to_os_string(),
+                None => values.original_name.to_os_string()
+            },
+            Placeholder::Ext => match Path::new(values.original_name).extension() {
+                Some(ext) => ext.to_os_string(),
+                None => OsString::new()
+            },
+            _ => OsString::from(render_text_placeholder(placeholder, values))
+        }
+    }
+
+    fn render_text_placeholder(placeholder: Placeholder, values: &TemplateValues) -> String {
+        match placeholder {
+            Placeholder::Year => values.date.format("%Y").to_string(),
+            Placeholder::Quarter => format!("{}", (values.date.month() - 1) / 3 + 1),
+            Placeholder::Month => values.date.format("%m").to_string(),
+            Placeholder::MonthName => String::from(values.month_name),
+            Placeholder::Day => values.date.format("%d").to_string(),
+            Placeholder::Hour => render_time(values.time, "%H"),
+            Placeholder::Minute => render_time(values.time, "%M"),
+            Placeholder::Second => render_time(values.time, "%S"),
+            Placeholder::Millisecond => render_time(values.time, "%3f"),
+            Placeholder::Date => values.date.format(DEST_DATE_FORMAT).to_string(),
+            Placeholder::DateTime => match values.time {
+                Some(time) => {
+                    let format = if values.with_milliseconds {
+                        DEST_DATETIME_WITH_MILLISECONDS_FORMAT
+                    } else { DEST_DATETIME_FORMAT };
+
+                    values.date.and_time(time).format(format).to_string()
+                }
+                None => values.date.format(DEST_DATE_FORMAT).to_string()
+            },
+            Placeholder::CameraModel => match values.camera_model {
+                Some(camera_model) if !camera_model.trim().is_empty() =>
+                    sanitize(camera_model.trim()),
+                _ => String::from(UNKNOWN_CAMERA_MODEL)
+            },
+            Placeholder::DateSource => String::from(values.date_source.name()),
+            Placeholder::OriginalName | Placeholder::Name | Placeholder::Ext => String::new()
+        }
+    }
+
+    fn render_time(time: Option<NaiveTime>, format: &str) -> String {
+        match time {
+            Some(time) => time.format(format).to_string(),
+            None => String::from(UNKNOWN_TIME)
+        }
+    }
+
+    /// Replace characters which can't be used in file names.
+    fn sanitize(value: &str) -> String {
+        value.chars()
+             .map(|c| if FORBIDDEN_CHARS.contains(&c) || c.is_control() { '_' } else { c })
+             .collect()
+    }
+}
@@ -0,0 +1,106 @@
+#[cfg(test)]
+pub mod template_tests {
+    use crate::template::template::{parse_template, DEFAULT_TEMPLATE, TemplateValues};
+    use crate::domain::domain::{DateSource, ExifDateTag};
+    use chrono::{NaiveDate, NaiveTime};
+    use std::ffi::OsStr;
+
+    #[test]
+    fn default_template_should_include_time_if_known() {
+        let template = parse_template(DEFAULT_TEMPLATE).unwrap();
+
+        let values = get_template_values(Some(NaiveTime::from_hms_opt(13, 12, 57).unwrap()));
+
+        assert_eq!(template.render(&values), "2019/Июль/2019-07-13__13-12-57__IMG39284.JPG");
+    }
+
+    #[test]
+    fn default_template_should_contain_date_only_if_time_is_unknown() {
+        let template = parse_template(DEFAULT_TEMPLATE).unwrap();
+
+        let values = get_template_values(None);
+
+        assert_eq!(template.render(&values), "2019/Июль/2019-07-13__IMG39284.JPG");
+    }
+
+    #[test]
+    fn all_placeholders_should_be_rendered() {
+        let template = parse_template(
+            "{year}/Q{quarter}/{camera_model}/{month}-{day}_{hour}-{minute}-{second}_{date_source}_{name}.{ext}"
+        ).unwrap();
+
+        let mut values = get_template_values(Some(NaiveTime::from_hms_opt(13, 12, 57).unwrap()));
+        values.camera_model = Some("Canon EOS 5D/Mark II");
+
+        assert_eq!(template.render(&values), "2019/Q3/Canon EOS 5D_Mark II/07-13_13-12-57_exif_IMG39284.JPG");
+    }
+
+    #[test]
+    fn milliseconds_should_be_included_into_datetime_if_requested() {
+        let template = parse_template(DEFAULT_TEMPLATE).unwrap();
+
+        let mut values = get_template_values(Some(NaiveTime::from_hms_milli_opt(13, 12, 57, 45).unwrap()));
+        values.with_milliseconds = true;
+
+        assert_eq!(template.render(&values), "2019/Июль/2019-07-13__13-12-57-045__IMG39284.JPG");
+
+        let template = parse_template("{date}__{hour}-{minute}-{second}.{millisecond}__{original_name}").unwrap();
+        assert_eq!(template.render(&values), "2019-07-13__13-12-57.045__IMG39284.JPG");
+    }
+
+    #[test]
+    fn unknown_time_should_be_rendered_as_unknown() {
+        let template = parse_template("{date}/{hour}-{minute}-{second}.{millisecond}__{original_name}").unwrap();
+
+        let values = get_template_values(None);
+
+        assert_eq!(template.render(&values), "2019-07-13/unknown-unknown-unknown.unknown__IMG39284.JPG");
+    }
+
+    #[test]
+    fn unknown_camera_model_should_be_rendered_as_unknown() {
+        let template = parse_template("{camera_model}/{original_name}").unwrap();
+
+        let values = get_template_values(None);
+
+        assert_eq!(template.render(&values), "Unknown/IMG39284.JPG");
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn original_name_should_be_kept_if_not_utf8() {
+        use std::os::unix::ffi::OsStrExt;
+
+        let template = parse_template("{year}/{name}_{day}.{ext}").unwrap();
+
+        let mut values = get_template_values(None);
+        values.original_name = OsStr::from_bytes(b"\xcf\xf0\xe8\xe2\xe5\xf2.jpg");
+
+        assert_eq!(template.render(&values).as_bytes(),
+                   &b"2019/\xcf\xf0\xe8\xe2\xe5\xf2_13.jpg"[..]);
+    }
+
+    #[test]
+    fn invalid_templates_should_be_rejected() {
+        assert!(parse_template("").is_err());
+        assert!(parse_template("{year}/{unknown}/{original_name}").is_err());
+        assert!(parse_template("{year}/{month_name/{original_name}").is_err());
+        assert!(parse_template("{year}}/{original_name}").is_err());
+        assert!(parse_template("/{year}/{original_name}").is_err());
+        assert!(parse_template("{year}/../{original_name}").is_err());
+        assert!(parse_template("{year}//{original_name}").is_err());
+        assert!(parse_template("{year}/photo.jpg").is_err());
+    }
+
+    fn get_template_values(time: Option<NaiveTime>) -> TemplateValues<'static> {
+        TemplateValues {
+            date: NaiveDate::from_ymd_opt(2019, 7, 13).unwrap(),
+            time,
+            month_name: "Июль",
+            camera_model: None,
+            original_name: OsStr::new("IMG39284.JPG"),
+            date_source: DateSource::Exif(ExifDateTag::DateTimeOriginal),
+            with_milliseconds: false
+        }
+    }
+}
@@ -0,0 +1,187 @@
+pub mod time_shift {
+    use chrono::Duration;
+    use std::fs;
+    use std::path::{Path, Component};
+
+    const MODEL_RULE_PREFIX: &str = "model:";
+    const DIR_RULE_PREFIX: &str = "dir:";
+    const COMMENT_PREFIX: char = '#';
+    const RULE_SEPARATOR: char = '=';
+
+    /// About 100 years, larger shifts are rejected
+    pub const MAX_TIME_SHIFT_HOURS: i64 = 876000;
+
+    /// What files time shift rule is applied to.
+    #[derive(Clone, PartialEq, Debug)]
+    pub enum TimeShiftMatcher {
+        /// EXIF `Model` property, case insensitive
+        CameraModel(String),
+
+        /// Name of any directory in file path, case insensitive
+        SourceDir(String)
+    }
+
+    #[derive(Clone, PartialEq, Debug)]
+    pub struct TimeShiftRule {
+        pub matcher: TimeShiftMatcher,
+        pub shift: Duration
+    }
+
+    /// Camera clock correction.
+    pub struct TimeShiftConfig {
+        /// Checked in order, first matched rule is applied
+        pub rules: Vec<TimeShiftRule>,
+
+        /// Applied if none of rules matched
+        pub default_shift: Option<Duration>
+    }
+
+    impl TimeShiftConfig {
+        pub fn to_string(&self) -> String {
+            let default_shift = match self.default_shift {
+                Some(shift) => format_time_shift(&shift),
+                None => String::from("none")
+            };
+
+            format!("rules: {}, default: {}", self.rules.len(), default_shift)
+        }
+
+        /// Get time shift for file.
+        pub fn get_time_shift(&self, file_path: &Path,
+                              camera_model: Option<&str>) -> Option<Duration> {
+            for rule in self.rules.iter() {
+                let matched = match &rule.matcher {
+                    TimeShiftMatcher::CameraModel(model) => match camera_model {
+                        Some(camera_model) =>
+                            camera_model.trim().to_lowercase() == model.to_lowercase(),
+                        None => false
+                    },
+                    TimeShiftMatcher::SourceDir(dir_name) => path_contains_dir(file_path, dir_name)
+                };
+
+                if matched {
+                    return Some(rule.shift)
+                }
+            }
+
+            self.default_shift
+        }
+    }
+
+    /// Parse time shift: `+01:00:00`, `-00:30`, `+48:00:00`. Format: [+|-]HH:MM[:SS]
+    /// Hours are limited with `MAX_TIME_SHIFT_HOURS`.
+    pub fn parse_time_shift(value: &str) -> Option<Duration> {
+        let value = value.trim();
+
+        let sign = match value.chars().next()? {
+            '+' => 1,
+            '-' => -1,
+            _ => return None
+        };
+
+        let parts: Vec<&str> = value[1..].split(':').collect();
+
+        if parts.len() < 2 || parts.len() > 3 {
+            return None
+        }
+
+        let hours: i64 = parts[0].parse().ok()?;
+        let minutes: i64 = parts[1].parse().ok()?;
+        let seconds: i64 = match parts.get(2) {
+            Some(seconds) => seconds.parse().ok()?,
+            None => 0
+        };
+
+        if !(0..=MAX_TIME_SHIFT_HOURS).contains(&hours) || !(0..=59).contains(&minutes) ||
+           !(0..=59).contains(&seconds) {
+            return None
+        }
+
+        let total_seconds = hours.checked_mul(3600)?
+            .checked_add(minutes * 60 + seconds)?
+            .checked_mul(sign)?;
+
+        Duration::try_seconds(total_seconds)
+    }
+
+    pub fn format_time_shift(shift: &Duration) -> String {
+        let total_seconds = shift.num_seconds();
+        let sign = if total_seconds < 0 { '-' } else { '+' };
+        let total_seconds = total_seconds.abs();
+
+        format!("{}{:02}:{:02}:{:02}", sign, total_seconds / 3600,
+                (total_seconds % 3600) / 60, total_seconds % 60)
+    }
+
+    /// Load rules from file. Each line: `model:<camera model> = <shift>` or
+    /// `dir:<directory name> = <shift>`. Lines started with `#` are ignored.
+    pub fn load_time_shift_rules(file_path: &str) -> Result<Vec<TimeShiftRule>, String> {
+        match fs::read_to_string(file_path) {
+            Ok(content) => parse_time_shift_rules(&content),
+            Err(e) => Err(format!("unable to read file '{}': {}", file_path, e))
+        }
+    }
+
+    pub fn parse_time_shift_rules(content: &str) -> Result<Vec<TimeShiftRule>, String> {
+        let mut results: Vec<TimeShiftRule> = Vec::new();
+
+        for (index, line) in content.lines().enumerate() {
+            let line = line.trim();
+
+            if line.is_empty() || line.starts_with(COMMENT_PREFIX) {
+                continue
+            }
+
+            let line_number = index + 1;
+
+            let separator_index = match line.rfind(RULE_SEPARATOR) {
+                Some(separator_index) => separator_index,
+                None => return Err(format!("line {}: '{}' expected", line_number, RULE_SEPARATOR))
+            };
+
+            let matcher_str = line[..separator_index].trim();
+            let shift_str = line[separator_index + 1..].trim();
+
+            let matcher = if matcher_str.to_lowercase().starts_with(MODEL_RULE_PREFIX) {
+                TimeShiftMatcher::CameraModel(
+                    String::from(matcher_str[MODEL_RULE_PREFIX.len()..].trim())
+                )
+
+            } else if matcher_str.to_lowercase().starts_with(DIR_RULE_PREFIX) {
+                TimeShiftMatcher::SourceDir(
+                    String::from(matcher_str[DIR_RULE_PREFIX.len()..].trim())
+                )
+
+            } else {
+                return Err(format!("line {}: rule should start with '{}' or '{}'",
+                                   line_number, MODEL_RULE_PREFIX, DIR_RULE_PREFIX))
+            };
+
+            let shift = match parse_time_shift(shift_str) {
+                Some(shift) => shift,
+                None => return Err(format!("line {}: invalid time shift '{}'",
+                                           line_number, shift_str))
+            };
+
+            results.push(TimeShiftRule { matcher, shift });
+        }
+
+        Ok(results)
+    }
+
+    fn path_contains_dir(file_path: &Path, dir_name: &str) -> bool {
+        let dir_name = dir_name.to_lowercase();
+
+        match file_path.parent() {
+            Some(parent) => parent.components().any(|component| {
+                match component {
+                    Component::Normal(name) => name.to_str()
+                        .map(|name| name.to_lowercase() == dir_name)
+                        .unwrap_or(false),
+                    _ => false
+                }
+            }),
+            None => false
+        }
+    }
+}
@@ -0,0 +1,74 @@
+#[cfg(test)]
+pub mod time_shift_tests {
+    use crate::time_shift::time_shift::{parse_time_shift, parse_time_shift_rules, format_time_shift,
+                                        TimeShiftConfig, TimeShiftMatcher};
+    use chrono::Duration;
+    use std::path::Path;
+
+    #[test]
+    fn time_shift_should_be_parsed() {
+        assert_eq!(parse_time_shift("+01:00:00").unwrap(), Duration::hours(1));
+        assert_eq!(parse_time_shift("-00:30").unwrap(), Duration::minutes(-30));
+        assert_eq!(parse_time_shift("+48:00:15").unwrap(), Duration::seconds(48 * 3600 + 15));
+
+        assert!(parse_time_shift("01:00:00").is_none());
+        assert!(parse_time_shift("+01").is_none());
+        assert!(parse_time_shift("+01:60").is_none());
+        assert!(parse_time_shift("+aa:00").is_none());
+
+        assert_eq!(parse_time_shift("-876000:00").unwrap(), Duration::hours(-876000));
+        assert!(parse_time_shift("+876001:00").is_none());
+        assert!(parse_time_shift("+9223372036854775807:00").is_none());
+    }
+
+    #[test]
+    fn time_shift_should_be_formatted() {
+        assert_eq!(format_time_shift(&Duration::minutes(-90)), "-01:30:00");
+        assert_eq!(format_time_shift(&Duration::seconds(5)), "+00:00:05");
+    }
+
+    #[test]
+    fn rules_should_be_parsed() {
+        let rules = parse_time_shift_rules(
+            "# comment\n\nmodel: Canon EOS 5D = +01:00:00\ndir:camera2=-00:30"
+        ).unwrap();
+
+        assert_eq!(rules.len(), 2);
+        assert_eq!(rules[0].matcher, TimeShiftMatcher::CameraModel(String::from("Canon EOS 5D")));
+        assert_eq!(rules[0].shift, Duration::hours(1));
+        assert_eq!(rules[1].matcher, TimeShiftMatcher::SourceDir(String::from("camera2")));
+        assert_eq!(rules[1].shift, Duration::minutes(-30));
+    }
+
+    #[test]
+    fn invalid_rules_should_be_rejected() {
+        assert!(parse_time_shift_rules("Canon = +01:00").is_err());
+        assert!(parse_time_shift_rules("model:Canon +01:00").is_err());
+        assert!(parse_time_shift_rules("model:Canon = 1 hour").is_err());
+    }
+
+    #[test]
+    fn first_matched_rule_should_be_used() {
+        let time_shift_config = TimeShiftConfig {
+            rules: parse_time_shift_rules(
+                "dir:camera2 = -00:30\nmodel:canon eos 5d = +01:00"
+            ).unwrap(),
+            default_shift: Some(Duration::hours(2))
+        };
+
+        assert_eq!(
+            time_shift_config.get_time_shift(Path::new("/mnt/Camera2/IMG_1.jpg"), Some("Canon EOS 5D")),
+            Some(Duration::minutes(-30))
+        );
+
+        assert_eq!(
+            time_shift_config.get_time_shift(Path::new("/mnt/camera1/IMG_1.jpg"), Some("Canon EOS 5D")),
+            Some(Duration::hours(1))
+        );
+
+        assert_eq!(
+            time_shift_config.get_time_shift(Path::new("/mnt/camera1/IMG_1.jpg"), None),
+            Some(Duration::hours(2))
+        );
+    }
+}
@@ -0,0 +1,318 @@
+pub mod video {
+    use std::convert::TryFrom;
+    use std::fs::File;
+    use std::io::{Read, Seek, SeekFrom};
+    use std::path::Path;
+    use chrono::{NaiveDate, NaiveDateTime, DateTime, FixedOffset, Duration};
+    use crate::domain::domain::{VideoDateTag, MetadataError};
+    use crate::containers::containers::{find_box, read_u32_be, read_u64_be, sniff_content_type,
+                                        ContentType, IMAGE_EXTENSIONS, RAW_EXTENSIONS};
+    use crate::files::files::read_file_header;
+
+    pub const VIDEO_EXTENSIONS: [&str; 3] = ["mp4", "mov", "3gp"];
+
+    const QUICKTIME_CREATION_DATE_KEY: &str = "com.apple.quicktime.creationdate";
+    const QUICKTIME_MODEL_KEY: &str = "com.apple.quicktime.model";
+
+    const QUICKTIME_DATETIME_FORMATS: [&str; 3] = [
+        "%Y-%m-%dT%H:%M:%S%z", "%Y-%m-%dT%H:%M:%S%.f%z", "%Y-%m-%dT%H:%M:%S%:z"
+    ];
+
+    /// `moov` is usually small, bigger boxes are considered as broken
+    const MAX_MOVIE_BOX_SIZE: u64 = 64 * 1024 * 1024;
+
+    /// Date of video from container metadata.
+    pub struct VideoDate {
+        /// Local time if offset is known, otherwise UTC
+        pub datetime: NaiveDateTime,
+
+        pub tag: VideoDateTag,
+
+        /// `Some(+00:00)` for header dates, which are stored in UTC
+        pub utc_offset: Option<FixedOffset>
+    }
+
+    pub struct VideoInfo {
+        pub date_created: Option<VideoDate>,
+        pub camera_model: Option<String>,
+
+        /// Date values with unsupported format
+        pub invalid_dates: Vec<String>
+    }
+
+    /// Detect video by extension. Content is checked for files with unknown extensions.
+    pub fn is_video_file(file_path: &Path) -> bool {
+        let ext = file_path.extension()
+                                      .and_then(|ext| ext.to_str())
+                                      .map(|ext| ext.to_lowercase())
+                                      .unwrap_or(String::new());
+
+        if VIDEO_EXTENSIONS.contains(&ext.as_str()) {
+            true
+
+        } else if IMAGE_EXTENSIONS.contains(&ext.as_str()) || RAW_EXTENSIONS.contains(&ext.as_str()) {
+            false
+
+        } else {
+            match read_file_header(file_path) {
+                Ok(header) => sniff_content_type(&header) == Some(ContentType::Video),
+                Err(_) => false
+            }
+        }
+    }
+
+    /// Read creation date from ISO-BMFF (MP4, MOV, 3GP) container.
+    /// Priority: QuickTime `creationdate`, `mvhd`, `tkhd` of first track with date.
+    pub fn get_video_info_from_file(file_path: &Path) -> Result<VideoInfo, MetadataError> {
+        info!("get video properties from '{}'", file_path.display());
+
+        let movie_box = match read_movie_box(file_path) {
+            Ok(Some(movie_box)) => movie_box,
+            Ok(None) => {
+                error!("'moov' box hasn't been found in '{}'", file_path.display());
+                return Err(MetadataError::NoMetadata(String::from("video without 'moov' box")))
+            }
+            Err(e) => {
+                error!("unable to read video file '{}': {}", file_path.display(), e);
+                return Err(e)
+            }
+        };
+
+        let mut result = VideoInfo { date_created: None, camera_model: None, invalid_dates: Vec::new() };
+
+        let metadata = get_quicktime_metadata(&movie_box);
+
+        for (key, value) in metadata.iter() {
+            debug!("{}: {}", key, value);
+
+            if key == QUICKTIME_MODEL_KEY && result.camera_model.is_none() {
+                result.camera_model = Some(value.to_string());
+            }
+
+            if key == QUICKTIME_CREATION_DATE_KEY && result.date_created.is_none() {
+                match parse_quicktime_datetime(value) {
+                    Some(datetime) => {
+                        result.date_created = Some(
+                            VideoDate {
+                                datetime: datetime.naive_local(),
+                                tag: VideoDateTag::QuickTimeCreationDate,
+                                utc_offset: Some(*datetime.offset())
+                            }
+                        )
+                    }
+                    None => {
+                        warn!("unsupported date format in '{}': '{}'", key, value);
+                        result.invalid_dates.push(format!("{}: '{}'", key, value));
+                    }
+                }
+            }
+        }
+
+        if result.date_created.is_none() {
+            result.date_created = get_header_date(&movie_box);
+        }
+
+        if let Some(video_date) = &result.date_created {
+            info!("date has been taken from '{}': {}", video_date.tag, video_date.datetime);
+        }
+
+        Ok(result)
+    }
+
+    /// Parse QuickTime date: `2020-10-10T12:09:47+0300`.
+    pub fn parse_quicktime_datetime(value: &str) -> Option<DateTime<FixedOffset>> {
+        let value = value.trim();
+
+        QUICKTIME_DATETIME_FORMATS.iter()
+            .find_map(|format| DateTime::parse_from_str(value, format).ok())
+    }
+
+    /// Header time: seconds since 1904-01-01 00:00:00 UTC. Zero means unknown.
+    pub fn get_header_datetime(seconds: u64) -> Option<NaiveDateTime> {
+        if seconds == 0 {
+            return None
+        }
+
+        let seconds = Duration::try_seconds(i64::try_from(seconds).ok()?)?;
+
+        NaiveDate::from_ymd_opt(1904, 1, 1)?.and_hms_opt(0, 0, 0)?
+            .checked_add_signed(seconds)
+    }
+
+    /// Find top-level `moov` box and read its content.
+    fn read_movie_box(file_path: &Path) -> Result<Option<Vec<u8>>, MetadataError> {
+        let mut file = File::open(file_path).map_err(to_unreadable)?;
+        let file_size = file.metadata().map_err(to_unreadable)?.len();
+
+        let mut offset: u64 = 0;
+
+        while get_box_end(offset, 8)? <= file_size {
+            file.seek(SeekFrom::Start(offset)).map_err(to_unreadable)?;
+
+            let mut header = [0u8; 16];
+            file.read_exact(&mut header[..8]).map_err(to_unreadable)?;
+
+            let size = u32::from_be_bytes([header[0], header[1], header[2], header[3]]) as u64;
+
+            let (header_size, box_size) = match size {
+                0 => (8, file_size - offset),
+                1 => {
+                    file.read_exact(&mut header[8..16]).map_err(to_unreadable)?;
+                    let mut large_size = [0u8; 8];
+                    large_size.copy_from_slice(&header[8..16]);
+                    (16, u64::from_be_bytes(large_size))
+                }
+                _ => (8, size)
+            };
+
+            if box_size < header_size {
+                return Ok(None)
+            }
+
+            let box_end = get_box_end(offset, box_size)?;
+
+            if &header[4..8] == b"moov" {
+                let content_size = box_size - header_size;
+
+                if content_size > MAX_MOVIE_BOX_SIZE || box_end > file_size {
+                    return Ok(None)
+                }
+
+                let mut content = vec![0u8; content_size as usize];
+                file.read_exact(&mut content).map_err(to_unreadable)?;
+
+                return Ok(Some(content))
+            }
+
+            offset = box_end;
+        }
+
+        Ok(None)
+    }
+
+    fn get_box_end(offset: u64, box_size: u64) -> Result<u64, MetadataError> {
+        offset.checked_add(box_size).ok_or_else(||
+            MetadataError::Unreadable(format!("invalid box size {} at offset {}", box_size, offset))
+        )
+    }
+
+    fn to_unreadable(error: std::io::Error) -> MetadataError {
+        MetadataError::Unreadable(error.to_string())
+    }
+
+    fn get_header_date(movie_box: &[u8]) -> Option<VideoDate> {
+        let utc = FixedOffset::east_opt(0)?;
+
+        if let Some((start, end)) = find_box(movie_box, 0, movie_box.len(), b"mvhd") {
+            if let Some(datetime) = read_creation_time(movie_box, start, end) {
+                return Some(
+                    VideoDate { datetime, tag: VideoDateTag::MovieHeader, utc_offset: Some(utc) }
+                )
+            }
+        }
+
+        let mut offset = 0;
+
+        while let Some((track_start, track_end)) = find_box(movie_box, offset, movie_box.len(), b"trak") {
+            if let Some((start, end)) = find_box(movie_box, track_start, track_end, b"tkhd") {
+                if let Some(datetime) = read_creation_time(movie_box, start, end) {
+                    return Some(
+                        VideoDate { datetime, tag: VideoDateTag::TrackHeader, utc_offset: Some(utc) }
+                    )
+                }
+            }
+
+            offset = track_end;
+        }
+
+        None
+    }
+
+    /// `mvhd` and `tkhd` are full boxes, creation time is the first field.
+    fn read_creation_time(contents: &[u8], start: usize, end: usize) -> Option<NaiveDateTime> {
+        let version = *contents.get(start)?;
+
+        let seconds = if version == 1 {
+            if start + 12 > end { return None }
+            read_u64_be(contents, start + 4)?
+        } else {
+            if start + 8 > end { return None }
+            read_u32_be(contents, start + 4)? as u64
+        };
+
+        get_header_datetime(seconds)
+    }
+
+    /// Read string values from `moov/meta` (`keys` + `ilst`).
+    fn get_quicktime_metadata(movie_box: &[u8]) -> Vec<(String, String)> {
+        let mut results: Vec<(String, String)> = Vec::new();
+
+        let (meta_start, meta_end) = match find_box(movie_box, 0, movie_box.len(), b"meta") {
+            Some(range) => range,
+            None => return results
+        };
+
+        // QuickTime `meta` isn't a full box, MP4 `meta` is
+        let meta_start = match movie_box.get(meta_start + 4..meta_start + 8) {
+            Some(box_type) if box_type == b"hdlr" => meta_start,
+            _ => meta_start + 4
+        };
+
+        let keys = match find_box(movie_box, meta_start, meta_end, b"keys") {
+            Some((start, end)) => read_keys(movie_box, start, end),
+            None => return results
+        };
+
+        let (ilst_start, ilst_end) = match find_box(movie_box, meta_start, meta_end, b"ilst") {
+            Some(range) => range,
+            None => return results
+        };
+
+        let mut offset = ilst_start;
+
+        while offset + 8 <= ilst_end {
+            let size = match read_u32_be(movie_box, offset) {
+                Some(size) if size >= 8 && offset + size as usize <= ilst_end => size as usize,
+                _ => break
+            };
+
+            // item type is 1-based index of key
+            let key_index = read_u32_be(movie_box, offset + 4).unwrap_or(0) as usize;
+
+            if key_index > 0 && key_index <= keys.len() {
+                if let Some((data_start, data_end)) = find_box(movie_box, offset + 8, offset + size, b"data") {
+                    // type indicator (1 - UTF-8) and locale
+                    if read_u32_be(movie_box, data_start) == Some(1) && data_start + 8 <= data_end {
+                        let value = String::from_utf8_lossy(&movie_box[data_start + 8..data_end]);
+                        results.push((keys[key_index - 1].clone(), value.to_string()));
+                    }
+                }
+            }
+
+            offset += size;
+        }
+
+        results
+    }
+
+    fn read_keys(contents: &[u8], start: usize, end: usize) -> Vec<String> {
+        let mut results: Vec<String> = Vec::new();
+
+        let entry_count = read_u32_be(contents, start + 4).unwrap_or(0);
+        let mut offset = start + 8;
+
+        for _ in 0..entry_count {
+            let size = match read_u32_be(contents, offset) {
+                Some(size) if size >= 8 && offset + size as usize <= end => size as usize,
+                _ => break
+            };
+
+            // namespace is skipped, usually `mdta`
+            results.push(String::from_utf8_lossy(&contents[offset + 8..offset + size]).to_string());
+
+            offset += size;
+        }
+
+        results
+    }
+}
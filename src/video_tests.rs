@@ -0,0 +1,151 @@
+#[cfg(test)]
+pub mod video_tests {
+    use crate::video::video::{get_video_info_from_file, parse_quicktime_datetime,
+                              get_header_datetime, is_video_file};
+    use crate::domain::domain::VideoDateTag;
+    use chrono::NaiveDate;
+    use std::{fs, env};
+    use std::path::Path;
+
+    #[test]
+    fn date_should_be_taken_from_movie_header() {
+        let contents = build_video(None);
+
+        let video_info = get_video_info(&contents, "mvhd.mp4");
+        let video_date = video_info.date_created.unwrap();
+
+        assert_eq!(video_date.tag, VideoDateTag::MovieHeader);
+        assert_eq!(video_date.datetime.format("%Y-%m-%d %H:%M:%S").to_string(), "2020-10-10 09:09:47");
+        assert_eq!(video_date.utc_offset.unwrap().local_minus_utc(), 0);
+        assert!(video_info.camera_model.is_none());
+    }
+
+    #[test]
+    fn quicktime_creation_date_should_have_priority() {
+        let contents = build_video(Some("2020-10-10T12:09:47+0300"));
+
+        let video_info = get_video_info(&contents, "creationdate.mov");
+        let video_date = video_info.date_created.unwrap();
+
+        assert_eq!(video_date.tag, VideoDateTag::QuickTimeCreationDate);
+        assert_eq!(video_date.datetime.format("%Y-%m-%d %H:%M:%S").to_string(), "2020-10-10 12:09:47");
+        assert_eq!(video_date.utc_offset.unwrap().local_minus_utc(), 3 * 3600);
+        assert_eq!(video_info.camera_model.unwrap(), "iPhone 12");
+    }
+
+    #[test]
+    fn error_should_be_returned_for_file_without_movie_box() {
+        let file_path = env::temp_dir().join("imgtag-video-test-without-moov.mp4");
+        fs::write(&file_path, build_box(b"ftyp", b"isom\0\0\0\0isom")).unwrap();
+
+        assert!(get_video_info_from_file(&file_path).is_err());
+
+        fs::remove_file(&file_path).unwrap();
+    }
+
+    #[test]
+    fn error_should_be_returned_for_box_size_overflow() {
+        let file_path = env::temp_dir().join("imgtag-video-test-box-size-overflow.mp4");
+
+        let mut contents = build_box(b"ftyp", b"isom\0\0\0\0isom");
+        contents.extend_from_slice(&1u32.to_be_bytes());
+        contents.extend_from_slice(b"free");
+        contents.extend_from_slice(&u64::MAX.to_be_bytes());
+        fs::write(&file_path, contents).unwrap();
+
+        assert!(get_video_info_from_file(&file_path).is_err());
+
+        fs::remove_file(&file_path).unwrap();
+    }
+
+    #[test]
+    fn quicktime_datetime_should_be_parsed() {
+        let datetime = parse_quicktime_datetime("2020-10-10T12:09:47+0300").unwrap();
+        assert_eq!(datetime.naive_local().format("%Y-%m-%d %H:%M:%S").to_string(), "2020-10-10 12:09:47");
+
+        assert!(parse_quicktime_datetime("2020-10-10T12:09:47-05:00").is_some());
+        assert!(parse_quicktime_datetime("2020-10-10").is_none());
+    }
+
+    #[test]
+    fn zero_header_time_should_be_ignored() {
+        assert!(get_header_datetime(0).is_none());
+        assert_eq!(get_header_datetime(1).unwrap().format("%Y-%m-%d %H:%M:%S").to_string(),
+                   "1904-01-01 00:00:01");
+        assert!(get_header_datetime(u64::MAX).is_none());
+    }
+
+    #[test]
+    fn video_files_should_be_detected_by_extension() {
+        assert!(is_video_file(Path::new("/tmp/VID_0001.MP4")));
+        assert!(is_video_file(Path::new("clip.mov")));
+        assert!(is_video_file(Path::new("clip.3gp")));
+        assert!(!is_video_file(Path::new("IMG_0001.jpg")));
+        assert!(!is_video_file(Path::new("mp4")));
+    }
+
+    fn get_video_info(contents: &[u8], file_name: &str) -> crate::video::video::VideoInfo {
+        let file_path = env::temp_dir().join(format!("imgtag-video-test-{}", file_name));
+        fs::write(&file_path, contents).unwrap();
+
+        let video_info = get_video_info_from_file(&file_path).unwrap();
+
+        fs::remove_file(&file_path).unwrap();
+
+        video_info
+    }
+
+    /// `ftyp`, `mdat` and `moov` with `mvhd` (2020-10-10 09:09:47 UTC) and optional QuickTime metadata.
+    fn build_video(creation_date: Option<&str>) -> Vec<u8> {
+        let mut results = build_box(b"ftyp", b"qt  \0\0\0\0qt  ");
+        results.extend_from_slice(&build_box(b"mdat", &[0; 32]));
+
+        let seconds = (NaiveDate::from_ymd_opt(2020, 10, 10).unwrap().and_hms_opt(9, 9, 47).unwrap() -
+            NaiveDate::from_ymd_opt(1904, 1, 1).unwrap().and_hms_opt(0, 0, 0).unwrap()).num_seconds() as u32;
+
+        let mut mvhd: Vec<u8> = vec![0, 0, 0, 0];
+        mvhd.extend_from_slice(&seconds.to_be_bytes());
+        mvhd.extend_from_slice(&seconds.to_be_bytes());
+        mvhd.extend_from_slice(&[0; 88]);
+
+        let mut moov = build_box(b"mvhd", &mvhd);
+
+        if let Some(creation_date) = creation_date {
+            let key_names = ["com.apple.quicktime.model", "com.apple.quicktime.creationdate"];
+            let values = ["iPhone 12", creation_date];
+
+            let mut keys: Vec<u8> = vec![0, 0, 0, 0];
+            keys.extend_from_slice(&(key_names.len() as u32).to_be_bytes());
+
+            let mut ilst: Vec<u8> = Vec::new();
+
+            for (index, key_name) in key_names.iter().enumerate() {
+                keys.extend_from_slice(&build_box(b"mdta", key_name.as_bytes()));
+
+                let mut data: Vec<u8> = 1u32.to_be_bytes().to_vec();
+                data.extend_from_slice(&[0; 4]);
+                data.extend_from_slice(values[index].as_bytes());
+
+                ilst.extend_from_slice(&build_box(&((index + 1) as u32).to_be_bytes(),
+                                                  &build_box(b"data", &data)));
+            }
+
+            let mut meta = build_box(b"hdlr", &[0; 25]);
+            meta.extend_from_slice(&build_box(b"keys", &keys));
+            meta.extend_from_slice(&build_box(b"ilst", &ilst));
+
+            moov.extend_from_slice(&build_box(b"meta", &meta));
+        }
+
+        results.extend_from_slice(&build_box(b"moov", &moov));
+
+        results
+    }
+
+    fn build_box(box_type: &[u8], data: &[u8]) -> Vec<u8> {
+        let mut results: Vec<u8> = ((data.len() + 8) as u32).to_be_bytes().to_vec();
+        results.extend_from_slice(box_type);
+        results.extend_from_slice(data);
+        results
+    }
+}